@@ -0,0 +1,280 @@
+use std::fmt;
+
+/// A pair of delimiters and what they're worth when scoring
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pair {
+    pub open: char,
+    /// Can be the same as open, then it closes when it's the innermost open
+    /// delimiter and opens otherwise
+    pub close: char,
+    /// Added to the syntax error score when the closer is the first error
+    pub error_score: u64,
+    /// Value of the closer when scoring completions
+    pub completion_score: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairTable {
+    pairs: Vec<Pair>,
+}
+
+impl PairTable {
+    pub fn new(pairs: Vec<Pair>) -> Self {
+        PairTable { pairs }
+    }
+
+    /// The four pairs from the puzzle
+    pub fn puzzle() -> Self {
+        let pair = |open, close, error_score, completion_score| Pair {
+            open,
+            close,
+            error_score,
+            completion_score,
+        };
+
+        PairTable::new(vec![
+            pair('(', ')', 3, 1),
+            pair('[', ']', 57, 2),
+            pair('{', '}', 1197, 3),
+            pair('<', '>', 25137, 4),
+        ])
+    }
+
+    fn opener(&self, ch: char) -> Option<usize> {
+        self.pairs.iter().position(|pair| pair.open == ch)
+    }
+
+    fn closer(&self, ch: char) -> Option<usize> {
+        self.pairs.iter().position(|pair| pair.close == ch)
+    }
+
+    /// Check a line and keep going past every error
+    pub fn lint(&self, line: &str) -> LintReport {
+        // Pair index of each delimiter still open
+        let mut open: Vec<usize> = Vec::new();
+        let mut errors = Vec::new();
+
+        for (i, found) in line.chars().enumerate() {
+            let column = i + 1;
+            let innermost = open.last().copied();
+
+            if innermost.map(|pair| self.pairs[pair].close) == Some(found) {
+                open.pop();
+                continue;
+            }
+
+            if let Some(pair) = self.opener(found) {
+                open.push(pair);
+                continue;
+            }
+
+            let (kind, recovery) = match (self.closer(found), innermost) {
+                (None, _) => (ErrorKind::Unknown, Recovery::Delete),
+                (Some(_), None) => (ErrorKind::Unopened, Recovery::Delete),
+                (Some(pair), Some(innermost)) => {
+                    let kind = ErrorKind::Mismatched {
+                        expected: self.pairs[innermost].close,
+                    };
+
+                    // Just one closer missing, put it back and close the next one out
+                    if open.len() >= 2 && open[open.len() - 2] == pair {
+                        open.truncate(open.len() - 2);
+                        (kind, Recovery::Insert(self.pairs[innermost].close.to_string()))
+                    } else {
+                        (kind, Recovery::Delete)
+                    }
+                }
+            };
+
+            errors.push(LintError {
+                column,
+                found,
+                kind,
+                recovery,
+            });
+        }
+
+        let completion = open.iter().rev().map(|pair| self.pairs[*pair].close).collect();
+
+        LintReport { errors, completion }
+    }
+
+    /// Score of the first closer that doesn't match, None if every closer did
+    pub fn error_score(&self, report: &LintReport) -> Option<u64> {
+        report
+            .errors
+            .iter()
+            .find(|err| err.kind != ErrorKind::Unknown)
+            .map(|err| self.pairs[self.closer(err.found).unwrap()].error_score)
+    }
+
+    /// Autocomplete score of an incomplete line that has no errors
+    pub fn completion_score(&self, report: &LintReport) -> Option<u64> {
+        if !report.is_incomplete() {
+            return None;
+        }
+
+        Some(report.completion.chars().fold(0, |score, ch| {
+            score * 5 + self.pairs[self.closer(ch).unwrap()].completion_score
+        }))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Closes something other than the innermost open delimiter
+    Mismatched { expected: char },
+    /// Closes when nothing is open
+    Unopened,
+    /// Not in the pair table
+    Unknown,
+}
+
+/// How to fix an error so checking can carry on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recovery {
+    /// Insert these closers before the error
+    Insert(String),
+    /// Remove the character
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintError {
+    /// 1-based position in the line
+    pub column: usize,
+    pub found: char,
+    pub kind: ErrorKind,
+    pub recovery: Recovery,
+}
+
+impl fmt::Display for LintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ErrorKind::Mismatched { expected } => write!(
+                f,
+                "{}: Expected {}, but found {} instead",
+                self.column, expected, self.found
+            )?,
+            ErrorKind::Unopened => write!(f, "{}: Found {} with nothing to close", self.column, self.found)?,
+            ErrorKind::Unknown => write!(f, "{}: Unknown character {}", self.column, self.found)?,
+        }
+
+        match &self.recovery {
+            Recovery::Insert(closers) => write!(f, ", insert {} before it", closers),
+            Recovery::Delete => write!(f, ", delete it"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintReport {
+    pub errors: Vec<LintError>,
+    /// Closers for everything still open at the end of the line, after recovering
+    pub completion: String,
+}
+
+impl LintReport {
+    pub fn is_incomplete(&self) -> bool {
+        self.errors.is_empty() && !self.completion.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ErrorKind, LintError, Pair, PairTable, Recovery};
+    use crate::test::get_test_input;
+
+    #[test]
+    fn test_example() {
+        let table = PairTable::puzzle();
+        let reports: Vec<_> = get_test_input().iter().map(|line| table.lint(line)).collect();
+
+        let error_score: u64 = reports.iter().filter_map(|r| table.error_score(r)).sum();
+        assert_eq!(error_score, 26397);
+
+        let mut completion_scores: Vec<u64> = reports.iter().filter_map(|r| table.completion_score(r)).collect();
+        completion_scores.sort_unstable();
+        assert_eq!(completion_scores.len(), 5);
+        assert_eq!(completion_scores[2], 288957);
+
+        assert_eq!(reports[0].completion, "}}]])})]");
+        assert_eq!(table.completion_score(&reports[0]), Some(288957));
+    }
+
+    #[test]
+    fn test_error_positions() {
+        let table = PairTable::puzzle();
+        let report = table.lint("{([(<{}[<>[]}>{[]{[(<()>");
+
+        assert_eq!(
+            report.errors[0],
+            LintError {
+                column: 13,
+                found: '}',
+                kind: ErrorKind::Mismatched { expected: ']' },
+                recovery: Recovery::Delete,
+            }
+        );
+        assert_eq!(
+            report.errors[0].to_string(),
+            "13: Expected ], but found } instead, delete it"
+        );
+
+        // Deleting the } leaves the > one closer short
+        assert_eq!(report.errors[1].column, 14);
+        assert_eq!(report.errors[1].recovery, Recovery::Insert("]".to_string()));
+    }
+
+    #[test]
+    fn test_recovery() {
+        let table = PairTable::puzzle();
+
+        let report = table.lint("[(])a");
+        assert_eq!(
+            report.errors.iter().map(|err| (err.column, err.recovery.clone())).collect::<Vec<_>>(),
+            vec![
+                (3, Recovery::Insert(")".to_string())),
+                (4, Recovery::Delete),
+                (5, Recovery::Delete),
+            ]
+        );
+        assert_eq!(report.errors[1].kind, ErrorKind::Unopened);
+        assert_eq!(report.errors[2].kind, ErrorKind::Unknown);
+        assert!(report.completion.is_empty());
+
+        // Recovered lines can still have something left open
+        let report = table.lint("<{)(");
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.completion, ")}>");
+        assert_eq!(table.completion_score(&report), None);
+    }
+
+    #[test]
+    fn test_custom_pairs() {
+        let table = PairTable::new(vec![
+            Pair {
+                open: '|',
+                close: '|',
+                error_score: 1,
+                completion_score: 1,
+            },
+            Pair {
+                open: '«',
+                close: '»',
+                error_score: 10,
+                completion_score: 2,
+            },
+        ]);
+
+        assert!(table.lint("|«||»|").errors.is_empty());
+
+        let report = table.lint("«|»");
+        assert_eq!(report.errors[0].kind, ErrorKind::Mismatched { expected: '|' });
+        assert_eq!(table.error_score(&report), Some(10));
+
+        let report = table.lint("«|«");
+        assert_eq!(report.completion, "»|»");
+        assert_eq!(table.completion_score(&report), Some(2 * 25 + 5 + 2));
+    }
+}
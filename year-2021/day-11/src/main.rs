@@ -1,10 +1,13 @@
+mod octopus;
+
 use std::{
-    collections::{HashSet, VecDeque},
-    io::{self, BufRead}, mem,
+    env,
+    io::{self, BufRead},
 };
 
-use nalgebra::DimMax;
-use ndarray::{s, Array1, Array2};
+use ndarray::{Array1, Array2};
+
+use octopus::{Neighborhood, OctopusGrid};
 
 type Point = nalgebra::Point2<i32>;
 type Vec2 = nalgebra::Vector2<i32>;
@@ -38,32 +41,6 @@ fn parse_input(mut reader: impl BufRead) -> Input {
     map
 }
 
-fn get_neighbors(p: Vec2, row_count: usize, col_count: usize) -> impl Iterator<Item=Vec2> {
-    let row_count = row_count as i32;
-    let col_count = col_count as i32;
-
-    vec![
-        p + Vec2::new(-1, 0),
-        p + Vec2::new(1, 0),
-        p + Vec2::new(0, -1),
-        p + Vec2::new(0, 1),
-        p + Vec2::new(-1, -1),
-        p + Vec2::new(1, 1),
-        p + Vec2::new(-1, 1),
-        p + Vec2::new(1, -1),
-    ].into_iter().filter(move |p| {
-        p[0] >= 0 && p[1] >= 0 && p[0] < row_count && p[1] < col_count
-    })
-}
-
-fn v((x, y): (usize, usize)) -> Vec2 {
-    Vec2::new(x as i32, y as i32)
-}
-
-fn p(v :Vec2) -> (usize, usize) {
-    (v[0] as usize, v[1] as usize)
-}
-
 fn main() {
     let input = {
         let stdin = io::stdin();
@@ -71,78 +48,33 @@ fn main() {
         parse_input(stdin_lock)
     };
 
-    dbg!(&input);
-
-    // for window in input.windows((3, 3)).into_iter() {
-    //     dbg!(window);
-    // }
-    let shape = input.shape();
-
-    let row_count = shape[0];
-    let col_count = shape[1];
-
-    let mut octopuses = input;
-    let mut flash_count = 0;
-
-    for step in 0..1000000 {
-        //let flashed = Array2::zeros((row_count, col_count));
-        let mut flashes = HashSet::new();
-
-        octopuses += 1;
+    let mut grid = OctopusGrid::new(input);
 
-        let mut new_flashes: Vec<Vec2> = octopuses
-            .indexed_iter()
-            .filter(|(pos, val)| **val > 9 && !flashes.contains(&v(*pos)))
-            .map(|(p, _)| v(p))
-            .collect();
-
-        for p in new_flashes.iter() {
-            flashes.insert(*p);
+    // `orthogonal` leaves out diagonal neighbors, `threshold=N` changes when they flash
+    for arg in env::args().skip(1) {
+        if arg == "orthogonal" {
+            grid = grid.with_neighborhood(Neighborhood::Orthogonal);
+        } else if let Some(threshold) = arg.strip_prefix("threshold=") {
+            grid = grid.with_threshold(threshold.parse().expect("Threshold should be a number"));
         }
+    }
 
-        let mut next_new_flashes: Vec<Vec2> = Vec::new();
-
-        while !new_flashes.is_empty() {
-            assert!(next_new_flashes.is_empty());
-
-            for pos in new_flashes.drain(..) {
-                flash_count += 1;
-                flashes.insert(pos);
-                //octopuses[p(pos)] = 0;
+    let start = grid.clone();
 
-                for neighbor in get_neighbors(pos, row_count, col_count) {
-                    if !flashes.contains(&neighbor) {
-                        octopuses[p(neighbor)] += 1;
+    let flash_count: usize = grid.steps().take(100).map(|flashed| flashed.len()).sum();
+    println!("flash count at 100 steps (part 1): {}", flash_count);
+    println!("energy after {} steps:\n{}", grid.steps_taken(), grid.energy());
 
-                        if octopuses[p(neighbor)] > 9 {
-                            flashes.insert(neighbor);
-                            next_new_flashes.push(neighbor);
-                        }
-                    }
-                }
+    match start.synchronization(1000000) {
+        Some(sync) => {
+            println!("first step all flash (part 2): {}", sync.first_step);
+            match sync.period {
+                Some(period) => println!("all flash again every {} steps", period),
+                None => println!("octopuses don't all flash again"),
             }
-
-            mem::swap(&mut next_new_flashes, &mut new_flashes);
-        }
-
-        for flashed_pos in flashes.iter() {
-            assert_eq!(octopuses[p(*flashed_pos)], 10);
-            octopuses[p(*flashed_pos)] = 0;
-        }
-
-        //dbg!(step, &octopuses);
-
-        if step == 99 {
-            println!("flash count at 100 steps (part 1): {}", flash_count);
-        }
-
-        if flashes.len() == col_count * row_count {
-            println!("first step all flash (part 2): {}", step + 1);
-            break;
         }
+        None => println!("octopuses never all flash"),
     }
-
-    //dbg!(flash_count);
 }
 
 #[cfg(test)]
@@ -151,7 +83,7 @@ mod test {
 
     use crate::{parse_input, Input};
 
-    fn get_test_input() -> Input {
+    pub(crate) fn get_test_input() -> Input {
         let test_data_str = include_str!("../test_input.txt");
 
         let test_data_reader = Cursor::new(test_data_str.to_owned());
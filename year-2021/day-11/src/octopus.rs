@@ -0,0 +1,274 @@
+use std::collections::HashSet;
+
+use crate::{Map, Vec2};
+
+type Cell = (usize, usize);
+
+/// Which cells get energy from a flash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighborhood {
+    /// Up, down, left and right
+    Orthogonal,
+    /// Orthogonal plus the four diagonals, the puzzle's neighborhood
+    Moore,
+}
+
+impl Neighborhood {
+    fn offsets(&self) -> Vec<Vec2> {
+        let orthogonal = [
+            Vec2::new(-1, 0),
+            Vec2::new(1, 0),
+            Vec2::new(0, -1),
+            Vec2::new(0, 1),
+        ];
+        let diagonal = [
+            Vec2::new(-1, -1),
+            Vec2::new(1, 1),
+            Vec2::new(-1, 1),
+            Vec2::new(1, -1),
+        ];
+
+        match self {
+            Neighborhood::Orthogonal => orthogonal.to_vec(),
+            Neighborhood::Moore => orthogonal.into_iter().chain(diagonal).collect(),
+        }
+    }
+}
+
+/// When every octopus first flashes at once and how often it happens after that
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Synchronization {
+    pub first_step: u64,
+    /// None if they didn't all flash again before giving up
+    pub period: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OctopusGrid {
+    energy: Map,
+    /// An octopus flashes once its energy goes above this
+    threshold: i32,
+    offsets: Vec<Vec2>,
+    steps_taken: u64,
+}
+
+impl OctopusGrid {
+    pub fn new(energy: Map) -> Self {
+        OctopusGrid {
+            energy,
+            threshold: 9,
+            offsets: Neighborhood::Moore.offsets(),
+            steps_taken: 0,
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: i32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn with_neighborhood(mut self, neighborhood: Neighborhood) -> Self {
+        self.offsets = neighborhood.offsets();
+        self
+    }
+
+    pub fn energy(&self) -> &Map {
+        &self.energy
+    }
+
+    pub fn steps_taken(&self) -> u64 {
+        self.steps_taken
+    }
+
+    fn neighbors(&self, (row, col): Cell) -> impl Iterator<Item = Cell> + '_ {
+        let (row_count, col_count) = self.energy.dim();
+
+        self.offsets.iter().filter_map(move |offset| {
+            let n_row = row as i32 + offset[0];
+            let n_col = col as i32 + offset[1];
+
+            (n_row >= 0 && n_col >= 0 && (n_row as usize) < row_count && (n_col as usize) < col_count)
+                .then_some((n_row as usize, n_col as usize))
+        })
+    }
+
+    /// Raise every octopus by one and let the flashes cascade. Returns every
+    /// octopus that flashed, they end the step with no energy.
+    pub fn step(&mut self) -> HashSet<Cell> {
+        self.energy += 1;
+        self.steps_taken += 1;
+
+        let mut to_flash: Vec<Cell> = self
+            .energy
+            .indexed_iter()
+            .filter(|(_, energy)| **energy > self.threshold)
+            .map(|(cell, _)| cell)
+            .collect();
+
+        let mut flashed: HashSet<Cell> = to_flash.iter().copied().collect();
+
+        while let Some(cell) = to_flash.pop() {
+            let neighbors: Vec<Cell> = self.neighbors(cell).collect();
+
+            for neighbor in neighbors {
+                self.energy[neighbor] += 1;
+
+                if self.energy[neighbor] > self.threshold && flashed.insert(neighbor) {
+                    to_flash.push(neighbor);
+                }
+            }
+        }
+
+        for cell in flashed.iter() {
+            self.energy[*cell] = 0;
+        }
+
+        flashed
+    }
+
+    /// Step forever, yielding what flashed each step
+    pub fn steps(&mut self) -> Steps<'_> {
+        Steps { grid: self }
+    }
+
+    /// Every octopus flashing means every octopus has no energy, so from then
+    /// on the grid repeats with the gap until the next time they all flash.
+    /// Gives up after `max_steps` steps from here.
+    pub fn synchronization(&self, max_steps: u64) -> Option<Synchronization> {
+        let mut grid = self.clone();
+        let size = grid.energy.len();
+        let mut first_step = None;
+
+        while grid.steps_taken - self.steps_taken < max_steps {
+            if grid.step().len() != size {
+                continue;
+            }
+
+            match first_step {
+                None => first_step = Some(grid.steps_taken),
+                Some(first_step) => {
+                    return Some(Synchronization {
+                        first_step,
+                        period: Some(grid.steps_taken - first_step),
+                    })
+                }
+            }
+        }
+
+        first_step.map(|first_step| Synchronization {
+            first_step,
+            period: None,
+        })
+    }
+}
+
+pub struct Steps<'a> {
+    grid: &'a mut OctopusGrid,
+}
+
+impl<'a> Iterator for Steps<'a> {
+    type Item = HashSet<Cell>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.grid.step())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ndarray::array;
+
+    use super::{Neighborhood, OctopusGrid, Synchronization};
+    use crate::test::get_test_input;
+
+    #[test]
+    fn test_example() {
+        let mut grid = OctopusGrid::new(get_test_input());
+
+        let flashes: usize = grid.steps().take(10).map(|flashed| flashed.len()).sum();
+        assert_eq!(flashes, 204);
+
+        let flashes: usize = flashes + grid.steps().take(90).map(|flashed| flashed.len()).sum::<usize>();
+        assert_eq!(flashes, 1656);
+        assert_eq!(grid.steps_taken(), 100);
+
+        assert_eq!(
+            OctopusGrid::new(get_test_input()).synchronization(1000),
+            Some(Synchronization {
+                first_step: 195,
+                period: Some(10),
+            })
+        );
+
+        // Counts from wherever the grid is, but steps stay absolute
+        assert_eq!(grid.synchronization(1000).unwrap().first_step, 195);
+        assert_eq!(grid.synchronization(50), None);
+
+        // The first full flash still counts when the next one is out of reach
+        assert_eq!(
+            OctopusGrid::new(get_test_input()).synchronization(200),
+            Some(Synchronization {
+                first_step: 195,
+                period: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_small_step() {
+        let mut grid = OctopusGrid::new(array![
+            [1, 1, 1, 1, 1],
+            [1, 9, 9, 9, 1],
+            [1, 9, 1, 9, 1],
+            [1, 9, 9, 9, 1],
+            [1, 1, 1, 1, 1],
+        ]);
+
+        let flashed = grid.step();
+        assert_eq!(flashed.len(), 9);
+        assert!(flashed.contains(&(2, 2)));
+        assert!(!flashed.contains(&(0, 0)));
+
+        assert_eq!(
+            grid.energy(),
+            array![
+                [3, 4, 5, 4, 3],
+                [4, 0, 0, 0, 4],
+                [5, 0, 0, 0, 5],
+                [4, 0, 0, 0, 4],
+                [3, 4, 5, 4, 3],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config() {
+        let energy = array![[6, 9, 6], [9, 5, 9], [6, 9, 6]];
+
+        // Without diagonals the corners only get pushed by two flashes
+        let mut grid = OctopusGrid::new(energy.clone()).with_neighborhood(Neighborhood::Orthogonal);
+        assert_eq!(grid.step().len(), 5);
+        assert_eq!(grid.energy()[(0, 0)], 9);
+
+        let mut grid = OctopusGrid::new(energy.clone());
+        assert_eq!(grid.step().len(), 9);
+
+        let mut grid = OctopusGrid::new(energy.clone()).with_threshold(10);
+        assert!(grid.step().is_empty());
+        assert_eq!(grid.step().len(), 9);
+
+        // A flash only reaches the next cell along
+        let mut grid = OctopusGrid::new(array![[9, 8, 8, 0]]).with_neighborhood(Neighborhood::Orthogonal);
+        assert_eq!(grid.step().len(), 3);
+        assert_eq!(grid.energy(), array![[0, 0, 0, 2]]);
+
+        let grid = OctopusGrid::new(array![[0, 0], [0, 0]]).with_threshold(4);
+        assert_eq!(
+            grid.synchronization(100),
+            Some(Synchronization {
+                first_step: 5,
+                period: Some(5),
+            })
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{Cave, Graph};
+
+impl fmt::Display for Cave {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cave::Start => write!(f, "start"),
+            Cave::End => write!(f, "end"),
+            Cave::Small(id) | Cave::Large(id) => write!(f, "{}", String::from_utf8_lossy(id)),
+        }
+    }
+}
+
+/// How often small caves can be passed through on a single path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VisitPolicy {
+    /// Most times any one small cave can be visited
+    pub max_visits: u32,
+    /// How many different small caves can be visited more than once
+    pub repeat_caves: u32,
+}
+
+impl VisitPolicy {
+    pub const PART_1: VisitPolicy = VisitPolicy {
+        max_visits: 1,
+        repeat_caves: 0,
+    };
+
+    pub const PART_2: VisitPolicy = VisitPolicy {
+        max_visits: 2,
+        repeat_caves: 1,
+    };
+
+    /// Whether a cave visited `visits` times already can be entered again
+    /// with `repeats` caves already visited more than once
+    fn can_enter(&self, visits: u32, repeats: u32) -> bool {
+        if visits >= self.max_visits {
+            return false;
+        }
+
+        match visits {
+            1 => repeats < self.repeat_caves,
+            _ => true,
+        }
+    }
+}
+
+/// The cave graph with every cave swapped for its index
+pub struct CaveSystem {
+    caves: Vec<Cave>,
+    neighbors: Vec<Vec<usize>>,
+    /// Index into the packed visit counts for each small cave
+    small_index: Vec<Option<usize>>,
+    start: usize,
+    end: usize,
+}
+
+impl CaveSystem {
+    pub fn new(graph: &Graph) -> Self {
+        let mut caves: Vec<Cave> = graph.nodes().collect();
+        caves.sort_unstable();
+
+        let index: HashMap<Cave, usize> = caves.iter().enumerate().map(|(i, cave)| (*cave, i)).collect();
+
+        let neighbors: Vec<Vec<usize>> = caves
+            .iter()
+            .map(|cave| {
+                let mut neighbors: Vec<usize> = graph.neighbors(*cave).map(|n| index[&n]).collect();
+                neighbors.sort_unstable();
+                neighbors
+            })
+            .collect();
+
+        for (cave, cave_neighbors) in caves.iter().zip(neighbors.iter()) {
+            if let Cave::Large(_) = cave {
+                assert!(
+                    cave_neighbors.iter().all(|n| !matches!(caves[*n], Cave::Large(_))),
+                    "Two connected large caves make infinitely many paths"
+                );
+            }
+        }
+
+        let mut small_count = 0;
+        let small_index = caves
+            .iter()
+            .map(|cave| {
+                cave.is_small().then(|| {
+                    small_count += 1;
+                    small_count - 1
+                })
+            })
+            .collect();
+
+        CaveSystem {
+            start: index[&Cave::Start],
+            end: index[&Cave::End],
+            caves,
+            neighbors,
+            small_index,
+        }
+    }
+
+    /// Count paths from start to end without listing them. Small cave visit
+    /// counts are packed into a u64 so every cave and set of counts is only
+    /// counted once.
+    pub fn path_count(&self, policy: VisitPolicy) -> u64 {
+        let bits_per_cave = 32 - policy.max_visits.leading_zeros();
+        let small_count = self.small_index.iter().flatten().count() as u32;
+
+        assert!(
+            small_count * bits_per_cave <= 64,
+            "Too many small caves to pack their visit counts"
+        );
+
+        let mut counter = Counter {
+            system: self,
+            policy,
+            bits_per_cave,
+            memo: HashMap::new(),
+        };
+
+        counter.count(self.start, 0, 0)
+    }
+
+    /// Every path from start to end in the order a depth first search finds them
+    pub fn paths(&self, policy: VisitPolicy) -> Paths<'_> {
+        Paths {
+            system: self,
+            policy,
+            path: vec![self.start],
+            next_neighbor: vec![0],
+            visits: vec![0; self.caves.len()],
+            repeats: 0,
+        }
+    }
+}
+
+struct Counter<'a> {
+    system: &'a CaveSystem,
+    policy: VisitPolicy,
+    bits_per_cave: u32,
+    memo: HashMap<(usize, u64), u64>,
+}
+
+impl<'a> Counter<'a> {
+    fn count(&mut self, cave: usize, visits: u64, repeats: u32) -> u64 {
+        // Repeats only depends on visits so it doesn't need to be part of the key
+        if let Some(count) = self.memo.get(&(cave, visits)) {
+            return *count;
+        }
+
+        let system = self.system;
+        let mask = (1 << self.bits_per_cave) - 1;
+        let mut count = 0;
+
+        for neighbor in system.neighbors[cave].iter().copied() {
+            if neighbor == system.start {
+                continue;
+            }
+
+            if neighbor == system.end {
+                count += 1;
+                continue;
+            }
+
+            match system.small_index[neighbor] {
+                Some(small) => {
+                    let shift = small as u32 * self.bits_per_cave;
+                    let cave_visits = ((visits >> shift) & mask) as u32;
+
+                    if self.policy.can_enter(cave_visits, repeats) {
+                        let repeats = if cave_visits == 1 { repeats + 1 } else { repeats };
+
+                        count += self.count(neighbor, visits + (1 << shift), repeats);
+                    }
+                }
+                None => count += self.count(neighbor, visits, repeats),
+            }
+        }
+
+        self.memo.insert((cave, visits), count);
+
+        count
+    }
+}
+
+/// Paths formatted like the puzzle, `start,A,b,end`
+pub struct Paths<'a> {
+    system: &'a CaveSystem,
+    policy: VisitPolicy,
+    path: Vec<usize>,
+    /// Next neighbor to try for each cave on the path
+    next_neighbor: Vec<usize>,
+    visits: Vec<u32>,
+    repeats: u32,
+}
+
+impl<'a> Paths<'a> {
+    fn format(&self) -> String {
+        let mut caves: Vec<String> = self.path.iter().map(|cave| self.system.caves[*cave].to_string()).collect();
+        caves.push(Cave::End.to_string());
+        caves.join(",")
+    }
+}
+
+impl<'a> Iterator for Paths<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let system = self.system;
+
+        loop {
+            let cave = *self.path.last()?;
+            let next = self.next_neighbor.last_mut()?;
+
+            let neighbor = match system.neighbors[cave].get(*next) {
+                Some(neighbor) => *neighbor,
+                None => {
+                    // Tried everything from here so step back
+                    if system.small_index[cave].is_some() {
+                        if self.visits[cave] == 2 {
+                            self.repeats -= 1;
+                        }
+                        self.visits[cave] -= 1;
+                    }
+
+                    self.path.pop();
+                    self.next_neighbor.pop();
+                    continue;
+                }
+            };
+
+            *next += 1;
+
+            if neighbor == system.start {
+                continue;
+            }
+
+            if neighbor == system.end {
+                return Some(self.format());
+            }
+
+            if system.small_index[neighbor].is_some() {
+                if !self.policy.can_enter(self.visits[neighbor], self.repeats) {
+                    continue;
+                }
+
+                self.visits[neighbor] += 1;
+                if self.visits[neighbor] == 2 {
+                    self.repeats += 1;
+                }
+            }
+
+            self.path.push(neighbor);
+            self.next_neighbor.push(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::io::Cursor;
+
+    use petgraph::graphmap::UnGraphMap;
+
+    use super::{CaveSystem, VisitPolicy};
+    use crate::{parse_input, Graph};
+
+    fn get_test_system(test_data_str: &str) -> CaveSystem {
+        let test_data_reader = Cursor::new(test_data_str.to_owned());
+
+        let graph: Graph = UnGraphMap::from_edges(parse_input(test_data_reader));
+
+        CaveSystem::new(&graph)
+    }
+
+    #[test]
+    fn test_examples() {
+        let system = get_test_system(include_str!("../test_input.txt"));
+        assert_eq!(system.path_count(VisitPolicy::PART_1), 19);
+        assert_eq!(system.path_count(VisitPolicy::PART_2), 103);
+
+        let system = get_test_system(include_str!("../test_input2.txt"));
+        assert_eq!(system.path_count(VisitPolicy::PART_1), 226);
+        assert_eq!(system.path_count(VisitPolicy::PART_2), 3509);
+    }
+
+    #[test]
+    fn test_paths() {
+        let system = get_test_system(include_str!("../test_input.txt"));
+
+        let paths: Vec<String> = system.paths(VisitPolicy::PART_1).collect();
+
+        assert_eq!(paths.len(), 19);
+        assert!(paths.contains(&"start,HN,dc,HN,kj,HN,end".to_string()));
+        assert!(paths.contains(&"start,kj,dc,end".to_string()));
+        assert!(paths.iter().all(|path| path.starts_with("start,") && path.ends_with(",end")));
+    }
+
+    #[test]
+    fn test_count_matches_paths() {
+        let system = get_test_system(include_str!("../test_input.txt"));
+
+        let policies = [
+            VisitPolicy::PART_1,
+            VisitPolicy::PART_2,
+            VisitPolicy {
+                max_visits: 3,
+                repeat_caves: 1,
+            },
+            VisitPolicy {
+                max_visits: 2,
+                repeat_caves: 3,
+            },
+            // No small caves at all
+            VisitPolicy {
+                max_visits: 0,
+                repeat_caves: 0,
+            },
+        ];
+
+        for policy in policies {
+            let paths: HashSet<String> = system.paths(policy).collect();
+
+            assert_eq!(paths.len() as u64, system.path_count(policy), "{:?}", policy);
+        }
+
+        let no_small = VisitPolicy {
+            max_visits: 0,
+            repeat_caves: 0,
+        };
+        assert_eq!(system.paths(no_small).collect::<Vec<_>>(), vec!["start,HN,end".to_string()]);
+    }
+}
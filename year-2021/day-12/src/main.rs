@@ -1,4 +1,8 @@
-use std::{io::{self, BufRead}, collections::HashSet};
+mod caves;
+
+use std::{env, io::{self, BufRead}};
+
+use caves::{CaveSystem, VisitPolicy};
 
 use petgraph::{graphmap::UnGraphMap, visit};
 
@@ -65,46 +69,6 @@ fn parse_input(mut reader: impl BufRead) -> Input {
     }).collect()
 }
 
-fn path_count(graph: &Graph, pos: Cave, visited: HashSet<Cave>, small_cave_visited: bool) -> usize {
-    let mut count = 0;
-
-    for neighbor in graph.neighbors(pos) {
-        let next_small_cave_visited = if visited.contains(&neighbor) && !small_cave_visited && neighbor.is_small() {
-            dbg!(neighbor);
-            true
-        }
-        else if visited.contains(&neighbor) {
-            continue;
-        }
-        else {
-            small_cave_visited
-        };
-
-        match neighbor {
-            Cave::End => {
-                count += 1;
-                //dbg!("End");
-            }
-            Cave::Start => {
-                continue;
-            }
-            Cave::Small(_) => {
-                let mut visited = visited.clone();
-                visited.insert(neighbor);
-                count += path_count(graph, neighbor, visited, next_small_cave_visited);
-                //dbg!(neighbor);
-            }
-            Cave::Large(_) => {
-                let visited = visited.clone();
-                count += path_count(graph, neighbor, visited, next_small_cave_visited);
-                //dbg!(neighbor);
-            }
-        }
-    }
-
-    count
-}
-
 fn main() {
     let input = {
         let stdin = io::stdin();
@@ -118,12 +82,19 @@ fn main() {
 
     //dbg!(&graph);
 
-    let paths = path_count(&graph, Cave::Start, HashSet::new(), true);
-    println!("{}", paths);
+    let system = CaveSystem::new(&graph);
 
+    // `paths` lists every part 1 path before the counts
+    if env::args().nth(1).as_deref() == Some("paths") {
+        for path in system.paths(VisitPolicy::PART_1) {
+            println!("{}", path);
+        }
+    }
 
+    let paths = system.path_count(VisitPolicy::PART_1);
+    println!("{}", paths);
 
-    let paths_part_2 = path_count(&graph, Cave::Start, HashSet::new(), false);
+    let paths_part_2 = system.path_count(VisitPolicy::PART_2);
     println!("{}", paths_part_2);
 }
 
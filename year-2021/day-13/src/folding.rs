@@ -0,0 +1,344 @@
+use std::fmt;
+
+use ndarray::Array2;
+
+use crate::{Fold, Vec2};
+
+/// A run of coordinates on the unfolded paper that all land in the same
+/// direction, `offset + c` or `offset - c` when flipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    start: i32,
+    /// Inclusive
+    end: i32,
+    flipped: bool,
+    offset: i32,
+}
+
+impl Segment {
+    fn image(&self, c: i32) -> i32 {
+        if self.flipped {
+            self.offset - c
+        } else {
+            self.offset + c
+        }
+    }
+}
+
+/// Where every coordinate along one axis ends up after all the folds along it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AxisMap {
+    /// Sorted by start and covering the unfolded paper apart from the creases
+    segments: Vec<Segment>,
+    /// Coordinates on the unfolded paper that end up on a fold line, with the
+    /// index of that fold
+    creases: Vec<(i32, usize)>,
+    /// Smallest coordinate on the folded paper, anything overhanging the
+    /// original edge is below zero
+    min: i32,
+    max: i32,
+}
+
+/// Why a dot can't be mapped onto the folded paper
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldError {
+    /// The dot ends up on the line of the fold at this index
+    DotOnFold { dot: Vec2, fold_index: usize },
+    /// The dot isn't on the paper
+    OutOfBounds { dot: Vec2 },
+}
+
+impl fmt::Display for FoldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FoldError::DotOnFold { dot, fold_index } => {
+                write!(f, "Dot {},{} is on the line of fold {}", dot[0], dot[1], fold_index + 1)
+            }
+            FoldError::OutOfBounds { dot } => write!(f, "Dot {},{} is off the paper", dot[0], dot[1]),
+        }
+    }
+}
+
+impl std::error::Error for FoldError {}
+
+impl AxisMap {
+    /// Paper `len` long along this axis, nothing folded yet
+    pub fn new(len: i32) -> Self {
+        AxisMap {
+            segments: vec![Segment {
+                start: 0,
+                end: len - 1,
+                flipped: false,
+                offset: 0,
+            }],
+            creases: Vec::new(),
+            min: 0,
+            max: len - 1,
+        }
+    }
+
+    /// Fold everything past `line` back over the rest. If that side is the
+    /// longer one it overhangs the edge and the paper grows in that direction.
+    fn fold(&mut self, line: i32, fold_index: usize) {
+        let mut segments = Vec::with_capacity(self.segments.len() + 2);
+
+        for segment in self.segments.iter() {
+            // The coordinate on this segment that lands on the line, may be outside it
+            let on_line = if segment.flipped {
+                segment.offset - line
+            } else {
+                line - segment.offset
+            };
+
+            let parts = [
+                (segment.start, segment.end.min(on_line - 1)),
+                (segment.start.max(on_line + 1), segment.end),
+            ];
+
+            for (start, end) in parts {
+                if start > end {
+                    continue;
+                }
+
+                let mut part = Segment { start, end, ..*segment };
+
+                if part.image(start) > line {
+                    part.flipped = !part.flipped;
+                    part.offset = 2 * line - part.offset;
+                }
+
+                segments.push(part);
+            }
+
+            if segment.start <= on_line && on_line <= segment.end {
+                self.creases.push((on_line, fold_index));
+            }
+        }
+
+        // Parts are pushed in order within each segment so this only moves
+        // the pieces left of a crease around
+        segments.sort_unstable_by_key(|segment| segment.start);
+        self.segments = segments;
+
+        let images = self
+            .segments
+            .iter()
+            .flat_map(|segment| [segment.image(segment.start), segment.image(segment.end)]);
+
+        let (min, max) = images.fold((i32::MAX, i32::MIN), |(min, max), c| (min.min(c), max.max(c)));
+        self.min = min;
+        self.max = max;
+    }
+
+    /// Length of the folded paper along this axis
+    pub fn len(&self) -> i32 {
+        (self.max - self.min + 1).max(0)
+    }
+
+    /// Where a coordinate on the unfolded paper ends up, counted from the
+    /// edge of the folded paper. Err is the index of the fold it lands on.
+    pub fn get(&self, c: i32) -> Option<Result<i32, usize>> {
+        let i = self.segments.partition_point(|segment| segment.end < c);
+
+        if let Some(segment) = self.segments.get(i).filter(|segment| segment.start <= c) {
+            return Some(Ok(segment.image(c) - self.min));
+        }
+
+        self.creases
+            .iter()
+            .find(|(crease, _)| *crease == c)
+            .map(|(_, fold_index)| Err(*fold_index))
+    }
+}
+
+/// A whole list of folds composed into one mapping per axis so every dot is
+/// moved in one go
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoldPlan {
+    x: AxisMap,
+    y: AxisMap,
+}
+
+impl FoldPlan {
+    pub fn compose(folds: &[Fold], size: Vec2) -> Self {
+        let mut x = AxisMap::new(size[0]);
+        let mut y = AxisMap::new(size[1]);
+
+        for (fold_index, fold) in folds.iter().enumerate() {
+            match *fold {
+                Fold::X(line) => x.fold(line, fold_index),
+                Fold::Y(line) => y.fold(line, fold_index),
+            }
+        }
+
+        FoldPlan { x, y }
+    }
+
+    /// Size of the folded paper
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.x.len(), self.y.len())
+    }
+
+    pub fn apply(&self, dot: Vec2) -> Result<Vec2, FoldError> {
+        let axis = |map: &AxisMap, c: i32| match map.get(c) {
+            Some(Ok(c)) => Ok(c),
+            Some(Err(fold_index)) => Err(FoldError::DotOnFold { dot, fold_index }),
+            None => Err(FoldError::OutOfBounds { dot }),
+        };
+
+        Ok(Vec2::new(axis(&self.x, dot[0])?, axis(&self.y, dot[1])?))
+    }
+}
+
+/// Which spots on the folded paper have a dot, indexed by row then column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DotGrid {
+    pub cells: Array2<bool>,
+}
+
+impl DotGrid {
+    pub fn dot_count(&self) -> usize {
+        self.cells.iter().filter(|dot| **dot).count()
+    }
+}
+
+impl fmt::Display for DotGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.cells.rows() {
+            for dot in row.iter() {
+                write!(f, "{}", if *dot { '#' } else { '.' })?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Paper {
+    dots: Vec<Vec2>,
+    size: Vec2,
+}
+
+impl Paper {
+    /// The paper is assumed to end just past the furthest dot on each axis
+    pub fn new(dots: Vec<Vec2>) -> Self {
+        let size = dots.iter().fold(Vec2::zeros(), |size, dot| size.sup(&(dot + Vec2::new(1, 1))));
+
+        Paper { dots, size }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    pub fn with_size(mut self, size: Vec2) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn fold(&self, folds: &[Fold]) -> Result<DotGrid, FoldError> {
+        let plan = FoldPlan::compose(folds, self.size);
+        let size = plan.size();
+
+        let mut cells = Array2::from_elem((size[1] as usize, size[0] as usize), false);
+
+        for dot in self.dots.iter() {
+            let folded = plan.apply(*dot)?;
+            cells[(folded[1] as usize, folded[0] as usize)] = true;
+        }
+
+        Ok(DotGrid { cells })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AxisMap, FoldError, FoldPlan, Paper};
+    use crate::test::get_test_input;
+    use crate::{Fold, Vec2};
+
+    #[test]
+    fn test_example() {
+        let (dots, folds) = get_test_input();
+        let paper = Paper::new(dots);
+
+        assert_eq!(paper.fold(&folds[..1]).unwrap().dot_count(), 17);
+
+        let grid = paper.fold(&folds).unwrap();
+        assert_eq!(grid.dot_count(), 16);
+        assert_eq!(
+            grid.to_string(),
+            "#####\n#...#\n#...#\n#...#\n#####\n.....\n.....\n"
+        );
+    }
+
+    #[test]
+    fn test_axis_composition() {
+        let mut map = AxisMap::new(15);
+        map.fold(7, 0);
+        map.fold(3, 1);
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(0), Some(Ok(0)));
+        assert_eq!(map.get(14), Some(Ok(0)));
+        assert_eq!(map.get(6), Some(Ok(0)));
+        assert_eq!(map.get(5), Some(Ok(1)));
+        assert_eq!(map.get(7), Some(Err(0)));
+        assert_eq!(map.get(3), Some(Err(1)));
+        assert_eq!(map.get(11), Some(Err(1)));
+        assert_eq!(map.get(15), None);
+
+        // Same thing one fold at a time
+        let reflect = |c: i32, line: i32| if c > line { 2 * line - c } else { c };
+        for c in (0..15).filter(|c| ![3, 7, 11].contains(c)) {
+            assert_eq!(map.get(c), Some(Ok(reflect(reflect(c, 7), 3))), "{}", c);
+        }
+    }
+
+    #[test]
+    fn test_overhang() {
+        // Folding at 2 on paper 0..=9 swings 3..=9 over to -5..=1
+        let mut map = AxisMap::new(10);
+        map.fold(2, 0);
+
+        assert_eq!(map.len(), 7);
+        assert_eq!(map.get(9), Some(Ok(0)));
+        assert_eq!(map.get(0), Some(Ok(5)));
+        assert_eq!(map.get(3), Some(Ok(6)));
+        assert_eq!(map.get(1), Some(Ok(6)));
+
+        let paper = Paper::new(vec![Vec2::new(0, 0), Vec2::new(9, 1)]).with_size(Vec2::new(10, 2));
+        let grid = paper.fold(&[Fold::X(2)]).unwrap();
+        assert_eq!(grid.to_string(), ".....#.\n#......\n");
+    }
+
+    #[test]
+    fn test_dot_on_fold() {
+        let paper = Paper::new(vec![Vec2::new(1, 0), Vec2::new(6, 4)]);
+
+        assert_eq!(
+            paper.fold(&[Fold::Y(4), Fold::X(4)]),
+            Err(FoldError::DotOnFold {
+                dot: Vec2::new(6, 4),
+                fold_index: 0
+            })
+        );
+
+        // 6 only lands on the line at 2 after folding at 4
+        assert_eq!(
+            paper.fold(&[Fold::X(4), Fold::X(2)]),
+            Err(FoldError::DotOnFold {
+                dot: Vec2::new(6, 4),
+                fold_index: 1
+            })
+        );
+
+        let plan = FoldPlan::compose(&[Fold::X(4)], Vec2::new(9, 5));
+        assert_eq!(
+            plan.apply(Vec2::new(9, 0)),
+            Err(FoldError::OutOfBounds { dot: Vec2::new(9, 0) })
+        );
+    }
+}
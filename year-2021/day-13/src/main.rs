@@ -1,12 +1,14 @@
-use std::{io::{self, BufRead}, ops::Index, collections::HashSet};
+mod folding;
+
+use std::io::{self, BufRead};
+
+use folding::Paper;
 
 type Vec2 = nalgebra::Vector2<i32>;
-use nalgebra::DimMax;
-use ndarray::{s, Array1, Array2};
 
 type Input = (Vec<Vec2>, Vec<Fold>);
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Fold {
     X(i32),
     Y(i32)
@@ -46,6 +48,24 @@ fn parse_input(mut reader: impl BufRead) -> Input {
     (positions, folds)
 }
 
+/// The first fold along each axis goes through the middle of the paper unless
+/// a dot is further out than that, an axis nobody folds along keeps
+/// `dots_size` from the furthest dot
+fn paper_size(folds: &[Fold], dots_size: Vec2) -> Vec2 {
+    let first_line = |along_x: bool| {
+        folds.iter().find_map(|fold| match *fold {
+            Fold::X(line) if along_x => Some(line),
+            Fold::Y(line) if !along_x => Some(line),
+            _ => None,
+        })
+    };
+
+    Vec2::new(
+        first_line(true).map_or(dots_size[0], |line| dots_size[0].max(2 * line + 1)),
+        first_line(false).map_or(dots_size[1], |line| dots_size[1].max(2 * line + 1)),
+    )
+}
+
 fn main() {
     let (input_positions, folds) = {
         let stdin = io::stdin();
@@ -53,101 +73,19 @@ fn main() {
         parse_input(stdin_lock)
     };
 
-    //dbg!(&input);
-
-    let mut positions: HashSet<Vec2> = input_positions.into_iter().collect();
-
-    for fold in folds[0..1].iter() {
-        let mut new_positions = HashSet::new();
-
-        for pos in positions.iter() {
-            match fold {
-                &Fold::X(fold_pos) => {
-                    let x = if pos[0] >= fold_pos {
-                        2*fold_pos - pos[0]
-                    }
-                    else {
-                        pos[0]
-                    };
-
-                    new_positions.insert(Vec2::new(x, pos[1]));
-                }
-                &Fold::Y(fold_pos) => {
-                    let y = if pos[1] >= fold_pos {
-                        2*fold_pos - pos[1]
-                    }
-                    else {
-                        pos[1]
-                    };
-
-                    new_positions.insert(Vec2::new(pos[0], y));
-                }
-            }
-        }
-
-        positions = new_positions;
-    }
-
-    println!("{}", positions.len());
-
-
-
-    for fold in folds.iter() {
-        let mut new_positions = HashSet::new();
-
-        for pos in positions.iter() {
-            match fold {
-                &Fold::X(fold_pos) => {
-                    let x = if pos[0] >= fold_pos {
-                        2*fold_pos - pos[0]
-                    }
-                    else {
-                        pos[0]
-                    };
-
-                    new_positions.insert(Vec2::new(x, pos[1]));
-                }
-                &Fold::Y(fold_pos) => {
-                    let y = if pos[1] >= fold_pos {
-                        2*fold_pos - pos[1]
-                    }
-                    else {
-                        pos[1]
-                    };
-
-                    new_positions.insert(Vec2::new(pos[0], y));
-                }
-            }
-        }
-
-        positions = new_positions;
-    }
-
-    let x_size = positions.iter().map(|pos| pos[0]).max().unwrap() + 1;
-    let y_size = positions.iter().map(|pos| pos[1]).max().unwrap() + 1;
+    let paper = Paper::new(input_positions);
+    let size = paper_size(&folds, paper.size());
+    let paper = paper.with_size(size);
 
-    let mut grid = Array2::zeros((y_size  as usize, x_size as usize));
-
-
-    for p in positions.iter() {
-        grid[(p[1] as usize, p[0] as usize)] = 1;
+    match paper.fold(&folds[..folds.len().min(1)]) {
+        Ok(grid) if !folds.is_empty() => println!("{}", grid.dot_count()),
+        Ok(_) => println!("no folds"),
+        Err(err) => println!("{}", err),
     }
 
-    //println!("{:?}", grid);
-
-    for row in 0..y_size {
-        for col in 0..x_size {
-            let p: Vec2 = Vec2::new(col as i32, row as i32);
-
-            if positions.contains(&p) {
-                print!("*")
-            }
-            else {
-                print!(" ");
-            }
-        }
-
-        println!("");
+    match paper.fold(&folds) {
+        Ok(grid) => print!("{}", grid),
+        Err(err) => println!("{}", err),
     }
 }
 
@@ -155,9 +93,9 @@ fn main() {
 mod test {
     use std::io::Cursor;
 
-    use crate::{parse_input, Input};
+    use crate::{paper_size, parse_input, Fold, Input, Paper, Vec2};
 
-    fn get_test_input() -> Input {
+    pub(crate) fn get_test_input() -> Input {
         let test_data_str = include_str!("../test_input.txt");
 
         let test_data_reader = Cursor::new(test_data_str.to_owned());
@@ -172,4 +110,21 @@ mod test {
         assert_eq!(positions.len(), 18);
         assert_eq!(folds.len(), 2);
     }
+
+    #[test]
+    fn test_paper_size() {
+        let dots_size = Vec2::new(11, 15);
+
+        assert_eq!(paper_size(&[Fold::Y(7), Fold::X(5)], dots_size), Vec2::new(11, 15));
+        assert_eq!(paper_size(&[Fold::Y(8), Fold::Y(3)], dots_size), Vec2::new(11, 17));
+        assert_eq!(paper_size(&[], dots_size), dots_size);
+
+        // Folding off the middle keeps the dots past the far side of the line
+        assert_eq!(paper_size(&[Fold::X(2), Fold::Y(3)], dots_size), Vec2::new(11, 15));
+
+        let paper = Paper::new(vec![Vec2::new(0, 0), Vec2::new(9, 1)]);
+        let size = paper_size(&[Fold::X(2)], paper.size());
+        let paper = paper.with_size(size);
+        assert_eq!(paper.fold(&[Fold::X(2)]).unwrap().to_string(), ".....#.\n#......\n");
+    }
 }
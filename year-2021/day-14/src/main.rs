@@ -1,4 +1,10 @@
-use std::{io::{self, BufRead}, collections::HashMap};
+use std::io::{self, BufRead};
+#[cfg(test)]
+use std::collections::HashMap;
+
+mod polymer;
+
+use polymer::{count_spread, PairMatrix};
 
 type Input = (String, Vec<Rule>);
 
@@ -26,8 +32,10 @@ fn parse_input(mut reader: impl BufRead) -> Input {
     (temalate, rules)
 }
 
+#[cfg(test)]
 type LetterCounts = HashMap<char, usize>;
 
+#[cfg(test)]
 fn merge_counts(a: Option<&LetterCounts>, b: Option<&LetterCounts>) -> LetterCounts {
     let mut merged_counts = LetterCounts::new();
 
@@ -39,6 +47,7 @@ fn merge_counts(a: Option<&LetterCounts>, b: Option<&LetterCounts>) -> LetterCou
     merged_counts
 }
 
+#[cfg(test)]
 /// Recursively counts expansions of rules, starting with a single pair from the polymer
 fn expand_and_count(rules: &Vec<Rule>, memo: &mut HashMap<(char, char, u8), LetterCounts>, a: char, b: char, depth: u8) {
     if memo.contains_key(&(a, b, depth))  {
@@ -77,7 +86,7 @@ fn expand_and_count(rules: &Vec<Rule>, memo: &mut HashMap<(char, char, u8), Lett
 
 
     let new_counts = if depth > 0 {
-        dbg!((a, b, depth - 1));
+        expand_and_count(rules, memo, a, b, depth - 1);
         memo.get(&(a, b, depth - 1)).unwrap().clone()
     }
     else {
@@ -87,17 +96,10 @@ fn expand_and_count(rules: &Vec<Rule>, memo: &mut HashMap<(char, char, u8), Lett
     memo.insert((a, b, depth), new_counts);
 }
 
-fn main() {
-    let (input_polymer, rules) = {
-        let stdin = io::stdin();
-        let stdin_lock = stdin.lock();
-        parse_input(stdin_lock)
-    };
-
-    dbg!(&input_polymer);
-
-    let mut polymer = input_polymer;
-
+#[cfg(test)]
+/// Count elements after steps insertion steps by expanding each pair of the
+/// polymer. Limited to 256 steps by the memo's depth.
+fn count_with_memo(polymer: &str, rules: &Vec<Rule>, steps: u8) -> LetterCounts {
     let mut element_counts = HashMap::new();
     let mut memo = HashMap::new();
 
@@ -106,73 +108,41 @@ fn main() {
         *el_count += 1;
     }
 
-    // for pass_count in 0..10 {
-    //     //let mut next_polymer = polymer.clone();
-    //     let mut insert_pos = 1;
-
-    //     let mut chars = polymer.chars().peekable();
-
-    //     let mut next_polymer_buf = String::with_capacity(polymer.len()*2);
-
-    //     while let (Some(ch_a), Some(ch_b)) = (chars.next(), chars.peek()) {
-    //         //dbg!(ch_a, ch_b);
-
-    //         next_polymer_buf.push(ch_a);
-
-    //         let pred = [
-    //             ch_a,
-    //             *ch_b
-    //         ];
-
-    //         for rule in rules.iter() {
-    //             if rule.0 == pred {
-    //                 //next_polymer.insert(insert_pos, rule.1);
-    //                 next_polymer_buf.push(rule.1);
-    //                 insert_pos += 1;
-
-    //                 let el_count = element_counts.entry(rule.1).or_insert(0);
-    //                 *el_count += 1;
-    //                 break;
-    //             }
-    //         }
-
-    //         insert_pos += 1;
-    //     }
-
-    //     next_polymer_buf.push(polymer.chars().last().unwrap());
-
-    //     //assert_eq!(&next_polymer, &next_polymer_buf);
-
-    //     polymer = next_polymer_buf;
-
-    //     println!("{}/40 {}", pass_count, polymer.len());
-    // }
+    if steps == 0 {
+        return element_counts;
+    }
 
     let mut chars = polymer.chars().peekable();
 
-    //let mut next_polymer_buf = String::with_capacity(polymer.len()*2);
-
-    let PASS_COUNT = 39;
-
     while let (Some(ch_a), Some(ch_b)) = (chars.next(), chars.peek()) {
-        expand_and_count(&rules, &mut memo, ch_a, *ch_b, PASS_COUNT);
+        expand_and_count(rules, &mut memo, ch_a, *ch_b, steps - 1);
 
-        let counts_output = memo.get(&(ch_a, *ch_b, PASS_COUNT));
+        let counts_output = memo.get(&(ch_a, *ch_b, steps - 1));
 
         element_counts = merge_counts(Some(&element_counts), counts_output);
-
-        println!("{}", memo.len());
     }
 
-    //dbg!(&polymer);
-    dbg!(&element_counts);
+    element_counts
+}
 
-    let mut counts_sorted: Vec<_> = element_counts.values().collect();
-    counts_sorted.sort();
+fn main() {
+    let (input_polymer, rules) = {
+        let stdin = io::stdin();
+        let stdin_lock = stdin.lock();
+        parse_input(stdin_lock)
+    };
 
-    dbg!(counts_sorted);
+    dbg!(&input_polymer);
 
+    let matrix = PairMatrix::new(&input_polymer, &rules);
 
+    for pair in matrix.unmatched_pairs() {
+        println!("warning: no rule for pair {}{}", pair[0], pair[1]);
+    }
+
+    for steps in [10, 40] {
+        println!("{} steps: {}", steps, count_spread(&matrix.element_counts(steps)));
+    }
 }
 
 #[cfg(test)]
@@ -181,7 +151,7 @@ mod test {
 
     use crate::{parse_input, Input};
 
-    fn get_test_input() -> Input {
+    pub(crate) fn get_test_input() -> Input {
         let test_data_str = include_str!("../test_input.txt");
 
         let test_data_reader = Cursor::new(test_data_str.to_owned());
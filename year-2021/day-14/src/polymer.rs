@@ -0,0 +1,257 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+use crate::Rule;
+
+pub type ElementCounts = BTreeMap<char, BigUint>;
+
+type Matrix = Vec<Vec<BigUint>>;
+
+fn identity(size: usize) -> Matrix {
+    (0..size)
+        .map(|row| (0..size).map(|col| BigUint::from((row == col) as u64)).collect())
+        .collect()
+}
+
+fn mul(a: &Matrix, b: &Matrix) -> Matrix {
+    let size = a.len();
+    let mut out = vec![vec![BigUint::zero(); size]; size];
+
+    for (row, a_row) in a.iter().enumerate() {
+        for (k, a_val) in a_row.iter().enumerate() {
+            if a_val.is_zero() {
+                continue;
+            }
+
+            for (col, b_val) in b[k].iter().enumerate() {
+                out[row][col] += a_val * b_val;
+            }
+        }
+    }
+
+    out
+}
+
+fn pow(matrix: &Matrix, mut exp: u64) -> Matrix {
+    let mut result = identity(matrix.len());
+    let mut base = matrix.clone();
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul(&result, &base);
+        }
+
+        exp >>= 1;
+
+        if exp > 0 {
+            base = mul(&base, &base);
+        }
+    }
+
+    result
+}
+
+/// Every pair that can appear in the polymer as a column of a matrix where
+/// each step maps a pair to the two pairs its insertion creates.
+/// Pairs without a rule map to themselves.
+pub struct PairMatrix {
+    pairs: Vec<[char; 2]>,
+    transitions: Matrix,
+    /// How many of each pair the template starts with
+    start: Vec<BigUint>,
+    /// Insertions only go between elements so the template's last element
+    /// stays last
+    last: Option<char>,
+    /// Reachable pairs that no rule matches
+    unmatched: Vec<[char; 2]>,
+}
+
+impl PairMatrix {
+    pub fn new(template: &str, rules: &[Rule]) -> Self {
+        let rule_map: HashMap<[char; 2], char> = rules.iter().map(|rule| (rule.0, rule.1)).collect();
+
+        let mut pair_index: HashMap<[char; 2], usize> = HashMap::new();
+        let mut pairs = Vec::new();
+        let mut queue: VecDeque<[char; 2]> = template_pairs(template).collect();
+
+        while let Some(pair) = queue.pop_front() {
+            if pair_index.contains_key(&pair) {
+                continue;
+            }
+
+            pair_index.insert(pair, pairs.len());
+            pairs.push(pair);
+
+            if let Some(insert) = rule_map.get(&pair) {
+                queue.push_back([pair[0], *insert]);
+                queue.push_back([*insert, pair[1]]);
+            }
+        }
+
+        let mut start = vec![BigUint::zero(); pairs.len()];
+        for pair in template_pairs(template) {
+            start[pair_index[&pair]] += 1u64;
+        }
+
+        let mut transitions = vec![vec![BigUint::zero(); pairs.len()]; pairs.len()];
+        let mut unmatched = Vec::new();
+
+        for (from, pair) in pairs.iter().enumerate() {
+            match rule_map.get(pair) {
+                Some(insert) => {
+                    transitions[pair_index[&[pair[0], *insert]]][from] += 1u64;
+                    transitions[pair_index[&[*insert, pair[1]]]][from] += 1u64;
+                }
+                None => {
+                    transitions[from][from] = BigUint::from(1u64);
+                    unmatched.push(*pair);
+                }
+            }
+        }
+
+        unmatched.sort_unstable();
+
+        PairMatrix {
+            pairs,
+            transitions,
+            start,
+            last: template.chars().last(),
+            unmatched,
+        }
+    }
+
+    /// Pairs that can show up in the polymer but no rule inserts anything between
+    pub fn unmatched_pairs(&self) -> &[[char; 2]] {
+        &self.unmatched
+    }
+
+    /// How many of each pair the polymer has after steps
+    pub fn pair_counts(&self, steps: u64) -> HashMap<[char; 2], BigUint> {
+        let power = pow(&self.transitions, steps);
+
+        let mut counts = HashMap::new();
+
+        for (row, pair) in power.iter().zip(self.pairs.iter()) {
+            let count: BigUint = row
+                .iter()
+                .zip(self.start.iter())
+                .map(|(factor, start_count)| factor * start_count)
+                .sum();
+
+            if !count.is_zero() {
+                counts.insert(*pair, count);
+            }
+        }
+
+        counts
+    }
+
+    /// Count each element after steps. Every element starts exactly one pair
+    /// except for the last element of the template which never changes.
+    pub fn element_counts(&self, steps: u64) -> ElementCounts {
+        let mut counts = ElementCounts::new();
+
+        for (pair, count) in self.pair_counts(steps) {
+            *counts.entry(pair[0]).or_insert_with(BigUint::zero) += count;
+        }
+
+        if let Some(last) = self.last {
+            *counts.entry(last).or_insert_with(BigUint::zero) += 1u64;
+        }
+
+        counts
+    }
+}
+
+fn template_pairs(template: &str) -> impl Iterator<Item = [char; 2]> + '_ {
+    template.chars().zip(template.chars().skip(1)).map(|(a, b)| [a, b])
+}
+
+/// Most common element count minus the least common
+pub fn count_spread(counts: &ElementCounts) -> BigUint {
+    match (counts.values().max(), counts.values().min()) {
+        (Some(max), Some(min)) => max - min,
+        _ => BigUint::zero(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::BigUint;
+
+    use super::{count_spread, PairMatrix};
+    use crate::test::get_test_input;
+    use crate::{count_with_memo, Rule};
+
+    #[test]
+    fn test_example() {
+        let (template, rules) = get_test_input();
+        let matrix = PairMatrix::new(&template, &rules);
+
+        let counts = matrix.element_counts(10);
+        assert_eq!(counts[&'B'], BigUint::from(1749u64));
+        assert_eq!(counts[&'H'], BigUint::from(161u64));
+        assert_eq!(count_spread(&counts), BigUint::from(1588u64));
+
+        let counts = matrix.element_counts(40);
+        assert_eq!(count_spread(&counts), BigUint::from(2188189693529u64));
+    }
+
+    #[test]
+    fn test_matches_memo() {
+        let (template, rules) = get_test_input();
+        let matrix = PairMatrix::new(&template, &rules);
+
+        for steps in [1, 5, 17] {
+            let memo_counts = count_with_memo(&template, &rules, steps);
+
+            let counts = matrix.element_counts(steps as u64);
+
+            assert_eq!(counts.len(), memo_counts.len());
+            for (ch, count) in memo_counts {
+                assert_eq!(counts[&ch], BigUint::from(count), "{} after {} steps", ch, steps);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pair_counts_length() {
+        let (template, rules) = get_test_input();
+        let matrix = PairMatrix::new(&template, &rules);
+
+        let pairs = matrix.pair_counts(5);
+
+        // The polymer doubles in length each step: 97 elements make 96 pairs
+        assert_eq!(pairs.values().sum::<BigUint>(), BigUint::from(96u64));
+    }
+
+    #[test]
+    fn test_many_steps() {
+        let (template, rules) = get_test_input();
+        let matrix = PairMatrix::new(&template, &rules);
+
+        // Well past what fits in a u128
+        for steps in [100, 200, 1000] {
+            let length: BigUint = matrix.element_counts(steps).values().sum();
+
+            assert_eq!(length, (BigUint::from(3u64) << steps) + 1u64, "{} steps", steps);
+        }
+    }
+
+    #[test]
+    fn test_unmatched_pairs() {
+        let rules = vec![Rule(['A', 'B'], 'C'), Rule(['A', 'C'], 'A')];
+        let matrix = PairMatrix::new("AB", &rules);
+
+        assert_eq!(matrix.unmatched_pairs(), &[['A', 'A'], ['C', 'B']]);
+
+        // Only AC keeps growing so after the first step each step adds one element
+        let counts = matrix.element_counts(3);
+        assert_eq!(counts.values().sum::<BigUint>(), BigUint::from(5u64));
+
+        let complete = PairMatrix::new(&get_test_input().0, &get_test_input().1);
+        assert!(complete.unmatched_pairs().is_empty());
+    }
+}
@@ -0,0 +1,41 @@
+use std::io::Cursor;
+use day_15::{find_path, find_path_dial, parse_input, wrap_risk, Input, Map, RiskMap};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId};
+
+fn get_test_input() -> Input {
+    let test_data_str = include_str!("../test_input.txt");
+
+    let test_data_reader = Cursor::new(test_data_str.to_owned());
+
+    parse_input(test_data_reader)
+}
+
+/// Same size as the real puzzle with risks that look random enough
+fn synthetic_map(size: usize) -> Map {
+    Map::from_shape_fn((size, size), |(row, col)| ((row * 31 + col * 17 + row * col * 7) % 9 + 1) as i32)
+}
+
+fn test_input(c: &mut Criterion) {
+    let map = get_test_input();
+    let risks = RiskMap::new(&map, 5, wrap_risk);
+
+    c.bench_function("test_input_heap", |b| b.iter(|| find_path(black_box(&risks))));
+    c.bench_function("test_input_dial", |b| b.iter(|| find_path_dial(black_box(&risks))));
+}
+
+fn synthetic_large(c: &mut Criterion) {
+    let map = synthetic_map(100);
+    let mut group = c.benchmark_group("synthetic_large");
+
+    for tiles in [1, 5] {
+        let risks = RiskMap::new(&map, tiles, wrap_risk);
+
+        group.bench_with_input(BenchmarkId::new("heap", tiles), &risks, |b, risks| b.iter(|| find_path(risks)));
+        group.bench_with_input(BenchmarkId::new("dial", tiles), &risks, |b, risks| b.iter(|| find_path_dial(risks)));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, test_input, synthetic_large);
+criterion_main!(benches);
@@ -0,0 +1,338 @@
+use std::{collections::BinaryHeap, io::BufRead};
+
+use ndarray::{Array1, Array2};
+use rustc_hash::FxHashMap;
+
+pub type Vec2 = nalgebra::Vector2<i32>;
+pub type Map = Array2<i32>;
+
+pub type Input = Map;
+
+pub type Cost = i32;
+
+pub fn parse_input(reader: impl BufRead) -> Input {
+    let map_vec: Vec<Array1<_>> = reader
+        .lines()
+        .map(|line| {
+            Array1::from_vec(
+                line.unwrap()
+                    .chars()
+                    .map(|ch| ch.to_digit(10).unwrap() as i32)
+                    .collect(),
+            )
+        })
+        .collect();
+
+    let width = map_vec[0].len();
+
+    let mut map = Map::zeros((0, width));
+
+    for row in map_vec.into_iter() {
+        map.push_row(row.view()).unwrap();
+    }
+
+    map
+}
+
+fn get_neighbors(p: Vec2, world_size: Vec2) -> impl Iterator<Item = Vec2> {
+    vec![
+        p + Vec2::new(-1, 0),
+        p + Vec2::new(1, 0),
+        p + Vec2::new(0, -1),
+        p + Vec2::new(0, 1),
+    ]
+    .into_iter()
+    .filter(move |p| p[0] >= 0 && p[1] >= 0 && p[0] < world_size[0] && p[1] < world_size[1])
+}
+
+/// The puzzle's wrap rule, risks above 9 start again at 1
+pub fn wrap_risk(unwrapped: Cost) -> Cost {
+    if unwrapped > 9 {
+        ((unwrapped - 1) % 9) + 1
+    } else {
+        unwrapped
+    }
+}
+
+/// The map repeated `tiles` times in each direction, each tile's risk raised
+/// by its distance from the top left tile and passed through `wrap`.
+/// Positions are row, column.
+pub struct RiskMap<'a, W> {
+    map: &'a Map,
+    tiles: i32,
+    wrap: W,
+}
+
+impl<'a, W: Fn(Cost) -> Cost> RiskMap<'a, W> {
+    pub fn new(map: &'a Map, tiles: i32, wrap: W) -> Self {
+        RiskMap { map, tiles, wrap }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        let (row_count, col_count) = self.map.dim();
+
+        Vec2::new(row_count as i32 * self.tiles, col_count as i32 * self.tiles)
+    }
+
+    /// Bottom right corner
+    pub fn goal(&self) -> Vec2 {
+        self.size() - Vec2::new(1, 1)
+    }
+
+    pub fn get_risk(&self, pos: Vec2) -> Cost {
+        let (row_count, col_count) = self.map.dim();
+        let (row_count, col_count) = (row_count as i32, col_count as i32);
+
+        let tile_risk = self.map[((pos[0] % row_count) as usize, (pos[1] % col_count) as usize)];
+        let extra_risk = pos[0] / row_count + pos[1] / col_count;
+
+        (self.wrap)(tile_risk + extra_risk)
+    }
+}
+
+/// The cheapest way from the top left to the goal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    /// Risk of everything entered, the start doesn't count
+    pub cost: Cost,
+    /// Start to goal inclusive
+    pub path: Vec<Vec2>,
+}
+
+fn trace_route(cost: Cost, goal: Vec2, previous_links: &FxHashMap<Vec2, Vec2>) -> Route {
+    let mut path = vec![goal];
+
+    while let Some(prev) = previous_links.get(path.last().unwrap()) {
+        path.push(*prev);
+    }
+
+    path.reverse();
+
+    Route { cost, path }
+}
+
+fn dist(a: Vec2, b: Vec2) -> Cost {
+    let d = b - a;
+
+    Cost::abs(d[0] as Cost) + Cost::abs(d[1] as Cost)
+}
+
+/// Every step costs at least 1 so this never overestimates as long as the
+/// wrap rule keeps risks positive
+fn h(a: Vec2, b: Vec2) -> Cost {
+    dist(a, b)
+}
+
+#[derive(Debug, Eq)]
+struct VisitItem {
+    estimated_cost: Cost,
+    cost: Cost,
+    pos: Vec2,
+}
+
+impl PartialEq for VisitItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.estimated_cost == other.estimated_cost
+    }
+}
+
+impl PartialOrd for VisitItem {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for VisitItem {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other.estimated_cost.cmp(&self.estimated_cost)
+    }
+}
+
+/// A* with a binary heap
+pub fn find_path<W: Fn(Cost) -> Cost>(risks: &RiskMap<W>) -> Route {
+    let start_pos = Vec2::new(0, 0);
+    let goal = risks.goal();
+    let world_size = risks.size();
+
+    let mut costs = FxHashMap::default();
+    let mut visit_queue = BinaryHeap::new();
+    let mut previous_links = FxHashMap::default();
+
+    visit_queue.push(VisitItem {
+        estimated_cost: h(start_pos, goal),
+        cost: 0,
+        pos: start_pos,
+    });
+    costs.insert(start_pos, 0);
+
+    while let Some(VisitItem { cost, pos, .. }) = visit_queue.pop() {
+        if pos == goal {
+            return trace_route(cost, goal, &previous_links);
+        }
+
+        // Already found a cheaper way here after this was queued
+        if cost > costs[&pos] {
+            continue;
+        }
+
+        for neighbor in get_neighbors(pos, world_size) {
+            let neighbor_cost = cost + risks.get_risk(neighbor);
+
+            if costs.get(&neighbor).is_none_or(|best| neighbor_cost < *best) {
+                costs.insert(neighbor, neighbor_cost);
+                previous_links.insert(neighbor, pos);
+
+                visit_queue.push(VisitItem {
+                    estimated_cost: neighbor_cost + h(neighbor, goal),
+                    cost: neighbor_cost,
+                    pos: neighbor,
+                });
+            }
+        }
+    }
+
+    unreachable!("The goal is always connected to the start")
+}
+
+/// Dijkstra with a bucket for every cost instead of a heap (Dial's
+/// algorithm). Risks are 1 to 9 so total costs stay small enough for a
+/// bucket each, and the cheapest position is always in the first
+/// non-empty bucket.
+pub fn find_path_dial<W: Fn(Cost) -> Cost>(risks: &RiskMap<W>) -> Route {
+    let start_pos = Vec2::new(0, 0);
+    let goal = risks.goal();
+    let world_size = risks.size();
+
+    let mut costs = FxHashMap::default();
+    let mut buckets: Vec<Vec<Vec2>> = vec![vec![start_pos]];
+    let mut previous_links = FxHashMap::default();
+
+    costs.insert(start_pos, 0);
+
+    let mut cost = 0;
+
+    while (cost as usize) < buckets.len() {
+        let pos = match buckets[cost as usize].pop() {
+            Some(pos) => pos,
+            None => {
+                cost += 1;
+                continue;
+            }
+        };
+
+        if pos == goal {
+            return trace_route(cost, goal, &previous_links);
+        }
+
+        if cost > costs[&pos] {
+            continue;
+        }
+
+        for neighbor in get_neighbors(pos, world_size) {
+            let neighbor_cost = cost + risks.get_risk(neighbor);
+
+            if costs.get(&neighbor).is_none_or(|best| neighbor_cost < *best) {
+                costs.insert(neighbor, neighbor_cost);
+                previous_links.insert(neighbor, pos);
+
+                if buckets.len() <= neighbor_cost as usize {
+                    buckets.resize_with(neighbor_cost as usize + 1, Vec::new);
+                }
+                buckets[neighbor_cost as usize].push(neighbor);
+            }
+        }
+    }
+
+    unreachable!("The goal is always connected to the start")
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use ndarray::Array2;
+    use proptest::prelude::*;
+
+    use crate::{find_path, find_path_dial, parse_input, wrap_risk, Cost, Input, RiskMap, Route, Vec2};
+
+    fn get_test_input() -> Input {
+        let test_data_str = include_str!("../test_input.txt");
+
+        let test_data_reader = Cursor::new(test_data_str.to_owned());
+
+        parse_input(test_data_reader)
+    }
+
+    /// Steps are all between neighbors and add up to the cost
+    fn check_route<W: Fn(Cost) -> Cost>(risks: &RiskMap<W>, route: &Route) {
+        assert_eq!(route.path.first(), Some(&Vec2::new(0, 0)));
+        assert_eq!(route.path.last(), Some(&risks.goal()));
+
+        for step in route.path.windows(2) {
+            let d = step[1] - step[0];
+            assert_eq!(d[0].abs() + d[1].abs(), 1);
+        }
+
+        let cost: Cost = route.path[1..].iter().map(|pos| risks.get_risk(*pos)).sum();
+        assert_eq!(cost, route.cost);
+    }
+
+    #[test]
+    fn test_example() {
+        let map = get_test_input();
+
+        let part_1 = RiskMap::new(&map, 1, wrap_risk);
+        let part_2 = RiskMap::new(&map, 5, wrap_risk);
+
+        for find in [find_path, find_path_dial] {
+            let route = find(&part_1);
+            assert_eq!(route.cost, 40);
+            check_route(&part_1, &route);
+
+            let route = find(&part_2);
+            assert_eq!(route.cost, 315);
+            check_route(&part_2, &route);
+        }
+    }
+
+    #[test]
+    fn test_tiling() {
+        let map = get_test_input();
+        let risks = RiskMap::new(&map, 5, wrap_risk);
+
+        assert_eq!(risks.size(), Vec2::new(50, 50));
+        assert_eq!(risks.get_risk(Vec2::new(0, 0)), 1);
+        assert_eq!(risks.get_risk(Vec2::new(0, 10)), 2);
+        assert_eq!(risks.get_risk(Vec2::new(10, 10)), 3);
+        // The 7 goes up to 9 then wraps back around to 1
+        assert_eq!(risks.get_risk(Vec2::new(0, 14)), 8);
+        assert_eq!(risks.get_risk(Vec2::new(0, 24)), 9);
+        assert_eq!(risks.get_risk(Vec2::new(0, 34)), 1);
+        assert_eq!(risks.get_risk(Vec2::new(49, 49)), 9);
+
+        let capped = RiskMap::new(&map, 5, |risk: Cost| risk.min(9));
+        assert_eq!(capped.get_risk(Vec2::new(0, 34)), 9);
+        assert_eq!(find_path(&capped).cost, find_path_dial(&capped).cost);
+    }
+
+    fn map_strategy() -> impl Strategy<Value = Array2<i32>> {
+        (1..8usize, 1..8usize).prop_flat_map(|(rows, cols)| {
+            prop::collection::vec(1..=9i32, rows * cols)
+                .prop_map(move |risks| Array2::from_shape_vec((rows, cols), risks).unwrap())
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_dial_matches_heap(map in map_strategy(), tiles in 1..4i32) {
+            let risks = RiskMap::new(&map, tiles, wrap_risk);
+
+            let heap_route = find_path(&risks);
+            let dial_route = find_path_dial(&risks);
+
+            assert_eq!(heap_route.cost, dial_route.cost);
+            check_route(&risks, &heap_route);
+            check_route(&risks, &dial_route);
+        }
+    }
+}
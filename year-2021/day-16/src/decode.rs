@@ -0,0 +1,454 @@
+use std::{fmt, io::{self, BufRead}};
+
+#[cfg(test)]
+use crate::BitSet;
+use crate::{Operator, Packet};
+
+/// Which part of a packet was being read when decoding failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Version,
+    TypeId,
+    LiteralGroup,
+    LengthType,
+    TotalLength,
+    PacketCount,
+}
+
+/// Every 3 bit type id has a meaning (4 is a literal, the rest are operators) so
+/// there is no unknown type error, instead operators are checked for their operand count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The transmission ended part way through field which started at offset
+    Truncated { offset: usize, field: Field },
+    /// A literal starting at offset has more groups than fit in a u64
+    LiteralOverflow { offset: usize, groups: usize },
+    /// A subpacket starting at offset ends at actual_end which is past the
+    /// declared_end given by its parent's length type 0 header
+    SubpacketOverrun {
+        offset: usize,
+        declared_end: usize,
+        actual_end: usize,
+    },
+    /// The operator starting at offset has the wrong number of subpackets for its type
+    InvalidOperandCount { offset: usize, ty: u32, count: usize },
+    /// A set bit was found at offset in the padding after the outermost packet
+    NonZeroPadding { offset: usize },
+    /// A character that is not a hex digit was found at offset
+    InvalidHexDigit { offset: usize, ch: char },
+    Io(io::ErrorKind),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Truncated { offset, field } => {
+                write!(f, "transmission ended reading {:?} starting at bit {}", field, offset)
+            }
+            DecodeError::LiteralOverflow { offset, groups } => {
+                write!(f, "literal at bit {} has too many groups ({})", offset, groups)
+            }
+            DecodeError::SubpacketOverrun {
+                offset,
+                declared_end,
+                actual_end,
+            } => write!(
+                f,
+                "subpacket at bit {} ends at bit {} past its parent's end at bit {}",
+                offset, actual_end, declared_end
+            ),
+            DecodeError::InvalidOperandCount { offset, ty, count } => write!(
+                f,
+                "operator type {} at bit {} cannot have {} subpackets",
+                ty, offset, count
+            ),
+            DecodeError::NonZeroPadding { offset } => {
+                write!(f, "non-zero padding at bit {}", offset)
+            }
+            DecodeError::InvalidHexDigit { offset, ch } => {
+                write!(f, "invalid hex digit {:?} at bit {}", ch, offset)
+            }
+            DecodeError::Io(kind) => write!(f, "error reading transmission: {}", kind),
+        }
+    }
+}
+
+/// A source of bits in transmission order
+pub trait BitSource {
+    fn next_bit(&mut self) -> Result<Option<bool>, DecodeError>;
+}
+
+/// Reads the unread bits of a BitSet
+#[cfg(test)]
+pub struct BitSetSource<'a> {
+    bits: &'a BitSet,
+    pos: usize,
+}
+
+#[cfg(test)]
+impl<'a> BitSetSource<'a> {
+    pub fn new(bits: &'a BitSet) -> Self {
+        BitSetSource {
+            bits,
+            pos: bits.offset as usize,
+        }
+    }
+}
+
+#[cfg(test)]
+impl<'a> BitSource for BitSetSource<'a> {
+    fn next_bit(&mut self) -> Result<Option<bool>, DecodeError> {
+        if self.pos >= self.bits.size {
+            return Ok(None);
+        }
+
+        let bit = self.bits.data[self.pos / 8] & (1 << (self.pos % 8)) != 0;
+        self.pos += 1;
+
+        Ok(Some(bit))
+    }
+}
+
+/// Reads hex digits from a reader one at a time so the whole transmission
+/// never has to be held in memory. The transmission ends at the first
+/// whitespace character or the end of the reader.
+pub struct HexSource<R> {
+    reader: R,
+    digit: u8,
+    bits_left: u32,
+    digits_read: usize,
+    finished: bool,
+}
+
+impl<R: BufRead> HexSource<R> {
+    pub fn new(reader: R) -> Self {
+        HexSource {
+            reader,
+            digit: 0,
+            bits_left: 0,
+            digits_read: 0,
+            finished: false,
+        }
+    }
+
+    fn next_digit(&mut self) -> Result<Option<u8>, DecodeError> {
+        let buf = self
+            .reader
+            .fill_buf()
+            .map_err(|err| DecodeError::Io(err.kind()))?;
+
+        let ch = match buf.first() {
+            Some(byte) => *byte as char,
+            None => return Ok(None),
+        };
+
+        if ch.is_ascii_whitespace() {
+            return Ok(None);
+        }
+
+        self.reader.consume(1);
+
+        let digit = ch.to_digit(16).ok_or(DecodeError::InvalidHexDigit {
+            offset: self.digits_read * 4,
+            ch,
+        })?;
+
+        self.digits_read += 1;
+
+        Ok(Some(digit as u8))
+    }
+}
+
+impl<R: BufRead> BitSource for HexSource<R> {
+    fn next_bit(&mut self) -> Result<Option<bool>, DecodeError> {
+        if self.bits_left == 0 {
+            if self.finished {
+                return Ok(None);
+            }
+
+            match self.next_digit()? {
+                Some(digit) => {
+                    self.digit = digit;
+                    self.bits_left = 4;
+                }
+                None => {
+                    self.finished = true;
+                    return Ok(None);
+                }
+            }
+        }
+
+        self.bits_left -= 1;
+
+        Ok(Some(self.digit & (1 << self.bits_left) != 0))
+    }
+}
+
+/// Most groups a literal can have and still fit in a u64
+const MAX_LITERAL_GROUPS: usize = 16;
+
+pub struct Decoder<S> {
+    source: S,
+    offset: usize,
+}
+
+impl<S: BitSource> Decoder<S> {
+    pub fn new(source: S) -> Self {
+        Decoder { source, offset: 0 }
+    }
+
+    /// Number of bits consumed so far
+    #[cfg(test)]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn read(&mut self, amount: u32, field: Field) -> Result<u32, DecodeError> {
+        let field_offset = self.offset;
+        let mut output = 0;
+
+        for _ in 0..amount {
+            let bit = self.source.next_bit()?.ok_or(DecodeError::Truncated {
+                offset: field_offset,
+                field,
+            })?;
+
+            output = (output << 1) | bit as u32;
+            self.offset += 1;
+        }
+
+        Ok(output)
+    }
+
+    /// Decode the next packet without looking at anything after it
+    pub fn decode_packet(&mut self) -> Result<Packet, DecodeError> {
+        let start = self.offset;
+        let version = self.read(3, Field::Version)?;
+        let ty = self.read(3, Field::TypeId)?;
+
+        if ty == 4 {
+            let mut nums: Vec<u8> = Vec::new();
+
+            loop {
+                let group = self.read(5, Field::LiteralGroup)?;
+
+                // Leading zero groups are dropped so they never count towards overflowing
+                if nums == [0] {
+                    nums.clear();
+                }
+
+                nums.push((group & 0xF) as u8);
+
+                if nums.len() > MAX_LITERAL_GROUPS {
+                    return Err(DecodeError::LiteralOverflow {
+                        offset: start,
+                        groups: nums.len(),
+                    });
+                }
+
+                if group & 0x10 == 0 {
+                    return Ok(Packet::Literal(version, nums));
+                }
+            }
+        }
+
+        let length_type = self.read(1, Field::LengthType)?;
+        let mut packets = Vec::new();
+
+        if length_type == 0 {
+            let total_length = self.read(15, Field::TotalLength)? as usize;
+            let declared_end = self.offset + total_length;
+
+            while self.offset < declared_end {
+                let offset = self.offset;
+                packets.push(self.decode_packet()?);
+
+                if self.offset > declared_end {
+                    return Err(DecodeError::SubpacketOverrun {
+                        offset,
+                        declared_end,
+                        actual_end: self.offset,
+                    });
+                }
+            }
+        } else {
+            let num_subpackets = self.read(11, Field::PacketCount)? as usize;
+
+            while packets.len() < num_subpackets {
+                packets.push(self.decode_packet()?);
+            }
+        }
+
+        let valid_count = match ty {
+            5..=7 => packets.len() == 2,
+            _ => !packets.is_empty(),
+        };
+
+        if !valid_count {
+            return Err(DecodeError::InvalidOperandCount {
+                offset: start,
+                ty,
+                count: packets.len(),
+            });
+        }
+
+        Ok(Packet::Operator(Operator {
+            ty,
+            version,
+            packets,
+        }))
+    }
+
+    /// Check that nothing but zero bits remain
+    pub fn finish(mut self) -> Result<(), DecodeError> {
+        while let Some(bit) = self.source.next_bit()? {
+            if bit {
+                return Err(DecodeError::NonZeroPadding { offset: self.offset });
+            }
+
+            self.offset += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Decode a complete transmission: one packet followed by zero padding
+pub fn decode_transmission(source: impl BitSource) -> Result<Packet, DecodeError> {
+    let mut decoder = Decoder::new(source);
+    let packet = decoder.decode_packet()?;
+    decoder.finish()?;
+    Ok(packet)
+}
+
+/// Decode a complete transmission streamed as hex from reader
+pub fn decode_reader(reader: impl BufRead) -> Result<Packet, DecodeError> {
+    decode_transmission(HexSource::new(reader))
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::{decode_reader, decode_transmission, BitSetSource, DecodeError, Field};
+    use crate::encode::{to_bitset, LengthType};
+    use crate::{parse_packet, parse_str, Packet};
+
+    fn decode_str(s: &str) -> Result<Packet, DecodeError> {
+        decode_reader(Cursor::new(s.to_owned()))
+    }
+
+    #[test]
+    fn test_matches_parse_packet() {
+        for hex in [
+            "D2FE28",
+            "38006F45291200",
+            "EE00D40C823060",
+            "8A004A801A8002F478",
+            "620080001611562C8802118E34",
+            "C0015000016115A2E0802F182340",
+            "A0016C880162017C3686B18A3D4780",
+            "9C0141080250320F1802104A08",
+        ] {
+            let expected = parse_packet(&mut parse_str(hex));
+
+            assert_eq!(decode_str(hex), Ok(expected.clone()));
+            assert_eq!(
+                decode_transmission(BitSetSource::new(&parse_str(hex))),
+                Ok(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_trailing_newline() {
+        assert_eq!(decode_str("D2FE28\n"), Ok(Packet::literal(6, 2021)));
+    }
+
+    #[test]
+    fn test_truncated_header() {
+        assert_eq!(
+            decode_str("D"),
+            Err(DecodeError::Truncated { offset: 3, field: Field::TypeId })
+        );
+    }
+
+    #[test]
+    fn test_truncated_literal_group() {
+        // 110 100 10111 11110 0 cut off in the middle of the third group
+        assert_eq!(
+            decode_str("D2FE"),
+            Err(DecodeError::Truncated { offset: 16, field: Field::LiteralGroup })
+        );
+    }
+
+    #[test]
+    fn test_literal_overflow() {
+        let packet = Packet::Literal(0, vec![1; 17]);
+        let bits = to_bitset(&packet, LengthType::TotalBits);
+
+        assert_eq!(
+            decode_transmission(BitSetSource::new(&bits)),
+            Err(DecodeError::LiteralOverflow { offset: 0, groups: 17 })
+        );
+    }
+
+    #[test]
+    fn test_leading_zero_groups_do_not_overflow() {
+        let mut nums = vec![0; 20];
+        nums.push(7);
+        let packet = Packet::Literal(0, nums);
+        let bits = to_bitset(&packet, LengthType::TotalBits);
+
+        let decoded = decode_transmission(BitSetSource::new(&bits)).unwrap();
+        assert_eq!(decoded, Packet::Literal(0, vec![7]));
+        assert_eq!(decoded.eval(), 7);
+        assert_eq!(packet.eval(), 7);
+
+        let zero = Packet::Literal(0, vec![0; 3]);
+        let bits = to_bitset(&zero, LengthType::TotalBits);
+        assert_eq!(decode_transmission(BitSetSource::new(&bits)), Ok(Packet::Literal(0, vec![0])));
+    }
+
+    #[test]
+    fn test_subpacket_overrun() {
+        // 38006F45291200 with the 27 bit subpacket length changed to 20
+        assert_eq!(
+            decode_str("38005345291200"),
+            Err(DecodeError::SubpacketOverrun {
+                offset: 33,
+                declared_end: 42,
+                actual_end: 49
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_operand_count() {
+        let packet: Packet = "(sum (lit 1))".parse().unwrap();
+        let mut bits = to_bitset(&packet, LengthType::PacketCount);
+        // Flip the type id from sum (000) to gt (101)
+        bits.data[0] |= 0b0010_1000;
+
+        assert_eq!(
+            decode_transmission(BitSetSource::new(&bits)),
+            Err(DecodeError::InvalidOperandCount { offset: 0, ty: 5, count: 1 })
+        );
+    }
+
+    #[test]
+    fn test_non_zero_padding() {
+        assert_eq!(
+            decode_str("D2FE29"),
+            Err(DecodeError::NonZeroPadding { offset: 23 })
+        );
+    }
+
+    #[test]
+    fn test_invalid_hex_digit() {
+        assert_eq!(
+            decode_str("D2XE28"),
+            Err(DecodeError::InvalidHexDigit { offset: 8, ch: 'X' })
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{BitSet, Operator, Packet};
+
+const TOTAL_BITS_WIDTH: u32 = 15;
+const PACKET_COUNT_WIDTH: u32 = 11;
+
+/// How an operator records the extent of its subpackets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthType {
+    /// Length type 0: 15 bit total length of the subpackets in bits
+    TotalBits,
+    /// Length type 1: 11 bit number of immediate subpackets
+    PacketCount,
+}
+
+fn encode_into(packet: &Packet, length_type: LengthType, out: &mut BitSet) {
+    match packet {
+        Packet::Literal(version, nums) => {
+            out.push_number(*version, 3);
+            out.push_number(4, 3);
+
+            // A literal always has at least one group even if it is zero
+            let groups: &[u8] = if nums.is_empty() { &[0] } else { nums };
+
+            for (n, num) in groups.iter().enumerate() {
+                let last = n == groups.len() - 1;
+                out.push_number(if last { 0 } else { 1 }, 1);
+                out.push_number(*num as u32, 4);
+            }
+        }
+        Packet::Operator(Operator {
+            ty,
+            version,
+            packets,
+        }) => {
+            out.push_number(*version, 3);
+            out.push_number(*ty, 3);
+
+            match length_type {
+                LengthType::TotalBits => {
+                    let mut subpackets = BitSet::new();
+
+                    for subpacket in packets {
+                        encode_into(subpacket, length_type, &mut subpackets);
+                    }
+
+                    assert!(
+                        subpackets.len() < 1 << TOTAL_BITS_WIDTH,
+                        "Subpackets too long for length type 0: {} bits",
+                        subpackets.len()
+                    );
+
+                    out.push_number(0, 1);
+                    out.push_number(subpackets.len() as u32, TOTAL_BITS_WIDTH);
+                    out.extend(&subpackets);
+                }
+                LengthType::PacketCount => {
+                    assert!(
+                        packets.len() < 1 << PACKET_COUNT_WIDTH,
+                        "Too many subpackets for length type 1: {}",
+                        packets.len()
+                    );
+
+                    out.push_number(1, 1);
+                    out.push_number(packets.len() as u32, PACKET_COUNT_WIDTH);
+
+                    for subpacket in packets {
+                        encode_into(subpacket, length_type, out);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Encode a packet tree using length_type for every operator
+pub fn to_bitset(packet: &Packet, length_type: LengthType) -> BitSet {
+    let mut bits = BitSet::new();
+    encode_into(packet, length_type, &mut bits);
+    bits
+}
+
+/// Encode a packet tree as a hex transmission padded with zeros to a whole
+/// number of bytes like the puzzle examples
+pub fn to_hex(packet: &Packet, length_type: LengthType) -> String {
+    let mut bits = to_bitset(packet, length_type);
+
+    let padding = (8 - bits.len() % 8) % 8;
+    for _ in 0..padding {
+        bits.push_bits(0, 1);
+    }
+
+    (0..bits.len())
+        .step_by(4)
+        .map(|pos| char::from_digit(bits.get(pos, 4), 16).unwrap().to_ascii_uppercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::{to_hex, LengthType};
+    use crate::test::packet_strategy;
+    use crate::{parse_packet, parse_str, Packet};
+
+    #[test]
+    fn test_literal_to_hex() {
+        assert_eq!(to_hex(&Packet::literal(6, 2021), LengthType::TotalBits), "D2FE28");
+    }
+
+    #[test]
+    fn test_examples_round_trip() {
+        let examples = [
+            ("38006F45291200", LengthType::TotalBits),
+            ("EE00D40C823060", LengthType::PacketCount),
+        ];
+
+        for (hex, length_type) in examples {
+            let packet = parse_packet(&mut parse_str(hex));
+            assert_eq!(to_hex(&packet, length_type), hex);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip_total_bits(packet in packet_strategy()) {
+            let hex = to_hex(&packet, LengthType::TotalBits);
+            prop_assert_eq!(parse_packet(&mut parse_str(&hex)), packet);
+        }
+
+        #[test]
+        fn test_round_trip_packet_count(packet in packet_strategy()) {
+            let hex = to_hex(&packet, LengthType::PacketCount);
+            prop_assert_eq!(parse_packet(&mut parse_str(&hex)), packet);
+        }
+    }
+}
@@ -3,6 +3,12 @@ use std::{
     io::{self, BufRead},
 };
 
+mod decode;
+mod encode;
+mod sexpr;
+
+use encode::LengthType;
+
 struct BitSet {
     offset: i64,
     size: usize,
@@ -102,33 +108,39 @@ impl BitSet {
         output as u32 & mask
     }
 
-    /// Split off a bitset that only extends to amount
-    fn read_bitset(&mut self, amount: u32) -> BitSet {
-        let ret = BitSet {
-            offset: self.offset,
-            size: usize::min(self.offset as usize + amount as usize, self.size),
-            data: self.data.clone()
-        };
+    fn len(&self) -> usize {
+        self.size - self.offset as usize
+    }
 
-        self.offset += amount as i64;
+    /// Push the low `width` bits of `value` most significant bit first,
+    /// which is the order numbers appear in a transmission
+    fn push_number(&mut self, value: u32, width: u32) {
+        if width == 0 {
+            return;
+        }
 
-        ret
-    }
+        let mut reversed = value.reverse_bits() >> (u32::BITS - width);
+        let mut remaining = width;
 
-    /// Read amount bits as a number and advance offset by amount
-    fn read(&mut self, amount: u32) -> u32 {
-        let ret = self.get(0, amount);
-        self.offset += amount as i64;
-        ret
+        while remaining > 0 {
+            let count = u32::min(remaining, 8);
+            self.push_bits((reversed & 0xFF) as u8, count as u8);
+            reversed = reversed.checked_shr(count).unwrap_or(0);
+            remaining -= count;
+        }
     }
 
-    fn len(&self) -> usize {
-        self.size - self.offset as usize
+    /// Append every unread bit of other to the end of this bitset
+    fn extend(&mut self, other: &BitSet) {
+        for bit in other.iter().skip(other.offset as usize) {
+            self.push_bits(bit as u8, 1);
+        }
     }
 }
 
-type Input = BitSet;
+type Input = Packet;
 
+#[cfg(test)]
 fn parse_str(s: &str) -> BitSet {
     let mut bitset = BitSet::new();
 
@@ -145,27 +157,47 @@ fn parse_str(s: &str) -> BitSet {
     bitset
 }
 
-fn parse_input(mut reader: impl BufRead) -> Input {
-    let mut buf = String::new();
-    reader.read_to_string(&mut buf).unwrap();
-
-    parse_str(buf.as_str())
+fn parse_input(reader: impl BufRead) -> Input {
+    decode::decode_reader(reader).unwrap_or_else(|err| panic!("Invalid transmission: {}", err))
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 struct Operator {
     ty: u32,
     version: u32,
     packets: Vec<Packet>
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 enum Packet {
     Literal(u32, Vec<u8>),
     Operator(Operator)
 }
 
+fn literal_value(nums: &[u8]) -> u64 {
+    nums.iter().fold(0, |output: u64, num| (output << 4) | *num as u64)
+}
+
 impl Packet {
+    /// Build a literal using the fewest 4 bit groups that can hold value
+    fn literal(version: u32, value: u64) -> Packet {
+        let mut nums = Vec::new();
+        let mut remaining = value;
+
+        loop {
+            nums.push((remaining & 0xF) as u8);
+            remaining >>= 4;
+
+            if remaining == 0 {
+                break;
+            }
+        }
+
+        nums.reverse();
+
+        Packet::Literal(version, nums)
+    }
+
     fn version_sum(&self) -> u32 {
         match self {
             Packet::Literal(v, _) => *v,
@@ -178,20 +210,7 @@ impl Packet {
 
     fn eval(&self) -> u64 {
         match self {
-            Packet::Literal(_v, nums) => {
-                let mut output: u64 = 0;
-
-                dbg!(nums.len()*4);
-
-                for (n, num) in nums.iter().rev().enumerate() {
-                    let shift: u64 = 4 * n as u64;
-                    output |= (*num as u64) << shift;
-                }
-
-                dbg!(output);
-
-                output
-            },
+            Packet::Literal(_v, nums) => literal_value(nums),
             Packet::Operator(op) => {
                 let mut subpackets = op.packets.iter().map(Packet::eval);
                 match op.ty {
@@ -226,77 +245,33 @@ impl Packet {
     }
 }
 
+/// Read one packet from msg and advance past it, panicking if it is malformed
+#[cfg(test)]
 fn parse_packet(msg: &mut BitSet) -> Packet {
-    dbg!(msg.len());
-    let version = msg.read(3);
-    let ty = msg.read(3);
-
-    dbg!(version, ty);
-
-    if ty == 4 {
-        let mut nums = Vec::new();
-        loop {
-            let last = msg.read(1);
-            let num = msg.read(4);
-
-            dbg!(last);
-            dbg!(num);
-
-            nums.push(num as u8);
+    let mut decoder = decode::Decoder::new(decode::BitSetSource::new(msg));
+    let packet = decoder
+        .decode_packet()
+        .unwrap_or_else(|err| panic!("Invalid packet: {}", err));
+    let consumed = decoder.offset();
 
-            if last == 0 {
-                return Packet::Literal(version, nums);
-            }
-        }
-    } else {
-        let length_type = msg.read(1);
-
-        dbg!(length_type);
-
-        // length_type 0 = 15 bit length
-        if length_type == 0 {
-            let subpacket_length_bits = msg.read(15);
+    msg.offset += consumed as i64;
 
-            dbg!(subpacket_length_bits);
-
-            let mut subpacket_msg = msg.read_bitset(subpacket_length_bits);
-
-            let mut packets = Vec::new();
-
-            while subpacket_msg.len() > 0 {
-                packets.push(parse_packet(&mut subpacket_msg));
-            }
-
-            return Packet::Operator(Operator { ty, version, packets });
-        }
-        else {
-            let num_subpackets = msg.read(11) as usize;
-
-            dbg!(num_subpackets);
-
-            let mut packets = Vec::new();
-
-            while packets.len() < num_subpackets {
-                packets.push(parse_packet(msg));
-            }
-
-            return Packet::Operator(Operator { ty, version, packets });
-        }
-    }
+    packet
 }
 
 fn main() {
-    let mut msg = {
+    let packet = {
         let stdin = io::stdin();
         let stdin_lock = stdin.lock();
         parse_input(stdin_lock)
     };
 
-    dbg!(&msg);
-
-    let packet = parse_packet(&mut msg);
     dbg!(&packet);
 
+    println!("expression: {}", packet);
+    println!("re-encoded (total bits): {}", encode::to_hex(&packet, LengthType::TotalBits));
+    println!("re-encoded (packet count): {}", encode::to_hex(&packet, LengthType::PacketCount));
+
     println!("version sum: {}", packet.version_sum());
 
     let evaled = packet.eval();
@@ -306,10 +281,25 @@ fn main() {
 
 #[cfg(test)]
 mod test {
+    use proptest::prelude::*;
     use std::io::Cursor;
 
     use crate::{parse_input, parse_str, BitSet, Input, parse_packet, Packet, Operator};
 
+    /// Arbitrary packet trees with valid operator arities
+    pub fn packet_strategy() -> impl Strategy<Value = Packet> {
+        let leaf = (0u32..8, any::<u64>()).prop_map(|(version, value)| Packet::literal(version, value));
+
+        leaf.prop_recursive(4, 32, 4, |inner| {
+            prop_oneof![
+                (0u32..8, prop::sample::select(vec![0u32, 1, 2, 3]), prop::collection::vec(inner.clone(), 1..4))
+                    .prop_map(|(version, ty, packets)| Packet::Operator(Operator { ty, version, packets })),
+                (0u32..8, prop::sample::select(vec![5u32, 6, 7]), prop::collection::vec(inner, 2))
+                    .prop_map(|(version, ty, packets)| Packet::Operator(Operator { ty, version, packets })),
+            ]
+        })
+    }
+
     fn get_test_input() -> Input {
         let test_data_str = include_str!("../test_input.txt");
 
@@ -322,6 +312,8 @@ mod test {
     fn test_parse() {
         let test_data = get_test_input();
 
+        assert_eq!(test_data.version_sum(), 16);
+
         // assert_eq!(
         //     test_data.data,
         //     vec![0x8A, 0x00, 0x4A, 0x80, 0x1A, 0x80, 0x02, 0xF4, 0x78]
@@ -0,0 +1,339 @@
+use std::{fmt, str::FromStr};
+
+use crate::{literal_value, Operator, Packet};
+
+const OPERATOR_NAMES: [(u32, &str); 7] = [
+    (0, "sum"),
+    (1, "product"),
+    (2, "min"),
+    (3, "max"),
+    (5, "gt"),
+    (6, "lt"),
+    (7, "eq"),
+];
+
+fn operator_name(ty: u32) -> Option<&'static str> {
+    OPERATOR_NAMES
+        .iter()
+        .find(|(op_ty, _)| *op_ty == ty)
+        .map(|(_, name)| *name)
+}
+
+fn operator_type(name: &str) -> Option<u32> {
+    if let Some((ty, _)) = OPERATOR_NAMES.iter().find(|(_, op_name)| *op_name == name) {
+        return Some(*ty);
+    }
+
+    // Operators without a mnemonic are written as op<type id>
+    name.strip_prefix("op")
+        .and_then(|ty| ty.parse().ok())
+        .filter(|ty| *ty < 8 && *ty != 4)
+}
+
+/// Number of operands an operator accepts as (min, max)
+fn operator_arity(ty: u32) -> (usize, usize) {
+    match ty {
+        5..=7 => (2, 2),
+        _ => (1, usize::MAX),
+    }
+}
+
+fn write_head(f: &mut fmt::Formatter<'_>, name: &str, version: u32) -> fmt::Result {
+    write!(f, "({}", name)?;
+
+    if version != 0 {
+        write!(f, "@{}", version)?;
+    }
+
+    Ok(())
+}
+
+/// Formats a packet as an S-expression such as `(sum (lit 1) (max (lit 2) (lit 3)))`.
+/// Non zero versions are written after the name e.g. `(lit@6 2021)`.
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Packet::Literal(version, nums) => {
+                write_head(f, "lit", *version)?;
+                write!(f, " {})", literal_value(nums))
+            }
+            Packet::Operator(Operator {
+                ty,
+                version,
+                packets,
+            }) => {
+                match operator_name(*ty) {
+                    Some(name) => write_head(f, name, *version)?,
+                    None => write_head(f, &format!("op{}", ty), *version)?,
+                }
+
+                for packet in packets {
+                    write!(f, " {}", packet)?;
+                }
+
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SexprError {
+    UnexpectedEnd,
+    UnexpectedToken { pos: usize, found: String },
+    UnknownOperator { pos: usize, name: String },
+    InvalidNumber { pos: usize, text: String },
+    WrongArity { pos: usize, name: String, count: usize },
+    TrailingInput { pos: usize },
+}
+
+impl fmt::Display for SexprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SexprError::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            SexprError::UnexpectedToken { pos, found } => {
+                write!(f, "unexpected '{}' at {}", found, pos)
+            }
+            SexprError::UnknownOperator { pos, name } => {
+                write!(f, "unknown operator '{}' at {}", name, pos)
+            }
+            SexprError::InvalidNumber { pos, text } => {
+                write!(f, "invalid number '{}' at {}", text, pos)
+            }
+            SexprError::WrongArity { pos, name, count } => {
+                write!(f, "'{}' at {} cannot take {} operands", name, pos, count)
+            }
+            SexprError::TrailingInput { pos } => write!(f, "trailing input at {}", pos),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token<'a> {
+    Open,
+    Close,
+    Atom(&'a str),
+}
+
+fn tokenize(s: &str) -> Vec<(usize, Token<'_>)> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((pos, ch)) = chars.next() {
+        match ch {
+            '(' => tokens.push((pos, Token::Open)),
+            ')' => tokens.push((pos, Token::Close)),
+            ch if ch.is_whitespace() => {}
+            _ => {
+                let mut end = pos + ch.len_utf8();
+
+                while let Some((next_pos, next)) = chars.peek().copied() {
+                    if next == '(' || next == ')' || next.is_whitespace() {
+                        break;
+                    }
+                    end = next_pos + next.len_utf8();
+                    chars.next();
+                }
+
+                tokens.push((pos, Token::Atom(&s[pos..end])));
+            }
+        }
+    }
+
+    tokens
+}
+
+struct Parser<'a> {
+    tokens: Vec<(usize, Token<'a>)>,
+    next: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&(usize, Token<'a>)> {
+        self.tokens.get(self.next)
+    }
+
+    fn advance(&mut self) -> Result<(usize, Token<'a>), SexprError> {
+        let token = self.peek().cloned().ok_or(SexprError::UnexpectedEnd)?;
+        self.next += 1;
+        Ok(token)
+    }
+
+    fn expect_atom(&mut self) -> Result<(usize, &'a str), SexprError> {
+        match self.advance()? {
+            (pos, Token::Atom(atom)) => Ok((pos, atom)),
+            (pos, Token::Open) => Err(SexprError::UnexpectedToken { pos, found: "(".to_string() }),
+            (pos, Token::Close) => Err(SexprError::UnexpectedToken { pos, found: ")".to_string() }),
+        }
+    }
+
+    fn parse_number<T: FromStr>(pos: usize, text: &str) -> Result<T, SexprError> {
+        text.parse().map_err(|_| SexprError::InvalidNumber {
+            pos,
+            text: text.to_string(),
+        })
+    }
+
+    fn parse_packet(&mut self) -> Result<Packet, SexprError> {
+        match self.advance()? {
+            (_, Token::Open) => {}
+            (pos, Token::Close) => {
+                return Err(SexprError::UnexpectedToken { pos, found: ")".to_string() })
+            }
+            (pos, Token::Atom(atom)) => {
+                return Err(SexprError::UnexpectedToken { pos, found: atom.to_string() })
+            }
+        }
+
+        let (head_pos, head) = self.expect_atom()?;
+
+        let (name, version) = match head.split_once('@') {
+            Some((name, version)) => {
+                let version: u32 = Self::parse_number(head_pos, version)?;
+
+                if version >= 8 {
+                    return Err(SexprError::InvalidNumber {
+                        pos: head_pos,
+                        text: version.to_string(),
+                    });
+                }
+
+                (name, version)
+            }
+            None => (head, 0),
+        };
+
+        if name == "lit" {
+            let (pos, value) = self.expect_atom()?;
+            let value: u64 = Self::parse_number(pos, value)?;
+
+            match self.advance()? {
+                (_, Token::Close) => return Ok(Packet::literal(version, value)),
+                (pos, Token::Open) => {
+                    return Err(SexprError::UnexpectedToken { pos, found: "(".to_string() })
+                }
+                (pos, Token::Atom(atom)) => {
+                    return Err(SexprError::UnexpectedToken { pos, found: atom.to_string() })
+                }
+            }
+        }
+
+        let ty = operator_type(name).ok_or_else(|| SexprError::UnknownOperator {
+            pos: head_pos,
+            name: name.to_string(),
+        })?;
+
+        let mut packets = Vec::new();
+
+        loop {
+            match self.peek() {
+                Some((_, Token::Close)) => {
+                    self.next += 1;
+                    break;
+                }
+                Some(_) => packets.push(self.parse_packet()?),
+                None => return Err(SexprError::UnexpectedEnd),
+            }
+        }
+
+        let (min, max) = operator_arity(ty);
+
+        if packets.len() < min || packets.len() > max {
+            return Err(SexprError::WrongArity {
+                pos: head_pos,
+                name: name.to_string(),
+                count: packets.len(),
+            });
+        }
+
+        Ok(Packet::Operator(Operator {
+            ty,
+            version,
+            packets,
+        }))
+    }
+}
+
+impl FromStr for Packet {
+    type Err = SexprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s),
+            next: 0,
+        };
+
+        let packet = parser.parse_packet()?;
+
+        if let Some((pos, _)) = parser.peek() {
+            return Err(SexprError::TrailingInput { pos: *pos });
+        }
+
+        Ok(packet)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::SexprError;
+    use crate::encode::{to_hex, LengthType};
+    use crate::test::packet_strategy;
+    use crate::{parse_packet, parse_str, Packet};
+
+    #[test]
+    fn test_display() {
+        let packet = parse_packet(&mut parse_str("C200B40A82"));
+
+        assert_eq!(packet.to_string(), "(sum@6 (lit@6 1) (lit@2 2))");
+    }
+
+    #[test]
+    fn test_parse_and_eval() {
+        let packet: Packet = "(sum (lit 1) (max (lit 2) (lit 7)) (gt (lit 3) (lit 1)))"
+            .parse()
+            .unwrap();
+
+        assert_eq!(packet.eval(), 9);
+        assert_eq!(packet.to_string(), "(sum (lit 1) (max (lit 2) (lit 7)) (gt (lit 3) (lit 1)))");
+    }
+
+    #[test]
+    fn test_hand_written_to_hex() {
+        let packet: Packet = "(eq (lit 2021) (product (lit 43) (lit 47)))".parse().unwrap();
+
+        let hex = to_hex(&packet, LengthType::PacketCount);
+
+        assert_eq!(parse_packet(&mut parse_str(&hex)).eval(), 1);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!("(sum (lit 1)".parse::<Packet>(), Err(SexprError::UnexpectedEnd));
+        assert_eq!(
+            "(nand (lit 1))".parse::<Packet>(),
+            Err(SexprError::UnknownOperator { pos: 1, name: "nand".to_string() })
+        );
+        assert_eq!(
+            "(lit x)".parse::<Packet>(),
+            Err(SexprError::InvalidNumber { pos: 5, text: "x".to_string() })
+        );
+        assert_eq!(
+            "(lt (lit 1))".parse::<Packet>(),
+            Err(SexprError::WrongArity { pos: 1, name: "lt".to_string(), count: 1 })
+        );
+        assert_eq!(
+            "(lit 1) (lit 2)".parse::<Packet>(),
+            Err(SexprError::TrailingInput { pos: 8 })
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip_sexpr(packet in packet_strategy()) {
+            let sexpr = packet.to_string();
+            prop_assert_eq!(sexpr.parse::<Packet>(), Ok(packet));
+        }
+    }
+}
@@ -1,14 +1,175 @@
-use nalgebra::DimMax;
-use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::io::{self, BufRead};
 
-type Point = nalgebra::Point2<i64>;
 type Vec2 = nalgebra::Vector2<i64>;
 
-type Input = Vec<i64>;
+/// Inclusive target area
+#[derive(Debug, Clone, PartialEq)]
+struct Target {
+    min: Vec2,
+    max: Vec2,
+}
+
+type Input = Target;
+
+fn parse_range(s: &str, axis: &str) -> (i64, i64) {
+    let range = s
+        .trim()
+        .strip_prefix(axis)
+        .and_then(|r| r.strip_prefix('='))
+        .unwrap_or_else(|| panic!("Expected {}=<min>..<max> got `{}`", axis, s));
+
+    let (a, b) = range
+        .split_once("..")
+        .unwrap_or_else(|| panic!("Expected <min>..<max> got `{}`", range));
+
+    let a: i64 = a.trim().parse().unwrap();
+    let b: i64 = b.trim().parse().unwrap();
+
+    (i64::min(a, b), i64::max(a, b))
+}
 
 fn parse_input(mut reader: impl BufRead) -> Input {
-    unimplemented!()
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+
+    let ranges = line
+        .trim()
+        .strip_prefix("target area:")
+        .unwrap_or_else(|| panic!("Expected `target area:` got `{}`", line.trim()));
+
+    let (x_str, y_str) = ranges.split_once(',').expect("Expected x and y ranges");
+
+    let (min_x, max_x) = parse_range(x_str, "x");
+    let (min_y, max_y) = parse_range(y_str, "y");
+
+    Target {
+        min: Vec2::new(min_x, min_y),
+        max: Vec2::new(max_x, max_y),
+    }
+}
+
+/// Steps (counting from 1) at which one axis of the probe is inside the target
+#[derive(Debug, Clone, Default, PartialEq)]
+struct StepSet {
+    steps: Vec<i64>,
+    /// The probe stops inside the target so every step from here on is a hit
+    stationary_from: Option<i64>,
+}
+
+/// Drag slows x down to zero after which x never changes
+fn x_steps(vx: i64, min_x: i64, max_x: i64) -> StepSet {
+    let mut step_set = StepSet::default();
+    let mut x = 0;
+    let mut vel = vx;
+    let mut step = 0;
+
+    while vel != 0 {
+        x += vel;
+        vel -= vel.signum();
+        step += 1;
+
+        if min_x <= x && x <= max_x {
+            step_set.steps.push(step);
+        }
+    }
+
+    if min_x <= x && x <= max_x {
+        step_set.stationary_from = Some(step + 1);
+    }
+
+    step_set
+}
+
+/// Gravity means y eventually falls forever so there are only ever a finite number of hits
+fn y_steps(vy: i64, min_y: i64, max_y: i64) -> Vec<i64> {
+    let mut steps = Vec::new();
+    let mut y = 0;
+    let mut vel = vy;
+    let mut step = 0;
+
+    // Once falling below the target it can never come back up
+    while !(y < min_y && vel < 0) {
+        y += vel;
+        vel -= 1;
+        step += 1;
+
+        if min_y <= y && y <= max_y {
+            steps.push(step);
+        }
+    }
+
+    steps
+}
+
+/// Every initial velocity that puts the probe in the target after some step,
+/// None if there are infinitely many.
+///
+/// Any velocity faster than the furthest edge of the target overshoots it on
+/// the first step (and on the way back down for y since the probe passes
+/// through the same heights it went up through) which bounds the search.
+fn valid_velocities(target: &Target) -> Option<Vec<Vec2>> {
+    let x_bound = i64::max(target.min[0].abs(), target.max[0].abs());
+    let mut y_bound = i64::max(target.min[1].abs(), target.max[1].abs());
+
+    let mut vxs_by_step: HashMap<i64, Vec<i64>> = HashMap::new();
+    let mut stationary_vxs: Vec<(i64, i64)> = Vec::new();
+
+    for vx in -x_bound..=x_bound {
+        let step_set = x_steps(vx, target.min[0], target.max[0]);
+
+        for step in step_set.steps {
+            vxs_by_step.entry(step).or_default().push(vx);
+        }
+
+        if let Some(from) = step_set.stationary_from {
+            stationary_vxs.push((from, vx));
+        }
+    }
+
+    // Every upward shot comes back down through y = 0 after 2 * vy + 1 steps
+    if target.min[1] <= 0 && 0 <= target.max[1] {
+        // Stopping inside the target means every shot high enough hits
+        if !stationary_vxs.is_empty() {
+            return None;
+        }
+
+        // Otherwise x is only ever inside early on, and shooting up faster
+        // than that many steps is still above the target by then
+        y_bound = i64::max(y_bound, vxs_by_step.keys().copied().max().unwrap_or(0));
+    }
+
+    let mut velocities = Vec::new();
+
+    for vy in -y_bound..=y_bound {
+        let mut vxs = HashSet::new();
+
+        for step in y_steps(vy, target.min[1], target.max[1]) {
+            vxs.extend(vxs_by_step.get(&step).into_iter().flatten().copied());
+            vxs.extend(
+                stationary_vxs
+                    .iter()
+                    .filter(|(from, _)| *from <= step)
+                    .map(|(_, vx)| *vx),
+            );
+        }
+
+        let mut vxs: Vec<i64> = vxs.into_iter().collect();
+        vxs.sort_unstable();
+
+        velocities.extend(vxs.into_iter().map(|vx| Vec2::new(vx, vy)));
+    }
+
+    Some(velocities)
+}
+
+/// Highest point reached, the probe starts at a height of 0
+fn max_height(vel: Vec2) -> i64 {
+    if vel[1] > 0 {
+        vel[1] * (vel[1] + 1) / 2
+    } else {
+        0
+    }
 }
 
 fn simulate_probe(pos: Vec2, vel: Vec2) -> (Vec2, Vec2) {
@@ -33,10 +194,17 @@ fn check_hit(target_min: Vec2, target_max: Vec2, x: i64, y: i64) -> Option<(Vec2
     let mut hit = None;
 
     loop {
+        // The probe has to be inside after a step, the launch position does not count
+        let (new_pos, new_vel) = simulate_probe(pos, vel);
+
+        pos = new_pos;
+        vel = new_vel;
+
+        max_y = i64::max(pos[1], max_y);
+
         //println!("pos={} vel={}", pos, vel);
         if pos[0] <= target_max[0] && pos[0] >= target_min[0] && pos[1] <= target_max[1] && pos[1] >= target_min[1] {
             hit = Some((initial_vel, pos, max_y));
-            print!(".");
             break;
         }
 
@@ -51,59 +219,47 @@ fn check_hit(target_min: Vec2, target_max: Vec2, x: i64, y: i64) -> Option<(Vec2
         if pos[0] > target_max[0] && vel[0] >= 0 {
             break;
         }
-
-        // if step == 10000 {
-        //     println!("LONG SIM: initial vel: {} pos: {} vel: {}", initial_vel, pos, vel);
-        // }
-
-        let (new_pos, new_vel) = simulate_probe(pos, vel);
-
-        pos = new_pos;
-        vel = new_vel;
-
-        max_y = i64::max(pos[1], max_y);
     }
 
     hit
 }
 
 fn main() {
-    let target_min = Vec2::new(81, -150);
-    let target_max = Vec2::new(129, -108);
-
-    let mut hits: Vec<(Vec2, Vec2, i64)> = (0..1000)
-        .into_par_iter()
-        .flat_map(|x| {
-            //         if x < 0 && target_min[0] > 0 {
-            //             continue;
-            //         }
-
-            (-1000i64..100000).into_par_iter().filter_map(move |y| {
-                check_hit(target_min, target_max, x, y)
-            })
-        })
-        .collect();
+    let target = {
+        let stdin = io::stdin();
+        let stdin_lock = stdin.lock();
+        parse_input(stdin_lock)
+    };
+
+    let velocities = match valid_velocities(&target) {
+        Some(velocities) => velocities,
+        None => {
+            println!("infinitely many velocities hit the target");
+            return;
+        }
+    };
 
-    println!("done");
+    println!("{} hits found", velocities.len());
 
-    println!("{} hits found", hits.len());
+    let best = velocities
+        .iter()
+        .copied()
+        .max_by_key(|vel| max_height(*vel))
+        .expect("No velocity hits the target");
 
-    hits.sort_by_key(|hit| hit.2);
-    hits.reverse();
+    let (_, hit_pos, height) = check_hit(target.min, target.max, best[0], best[1]).unwrap();
 
-    for hit in &hits[..10] {
-        println!(
-            "initial vel: {} hit pos: {} max height: {}",
-            hit.0, hit.1, hit.2
-        );
-    }
+    println!(
+        "initial vel: {} hit pos: {} max height: {}",
+        best, hit_pos, height
+    );
 }
 
 #[cfg(test)]
 mod test {
     use std::io::Cursor;
 
-    use crate::{parse_input, Input};
+    use crate::{check_hit, max_height, parse_input, valid_velocities, Input, Target, Vec2};
 
     fn get_test_input() -> Input {
         let test_data_str = include_str!("../test_input.txt");
@@ -113,8 +269,78 @@ mod test {
         parse_input(test_data_reader)
     }
 
+    /// Simulate every velocity in a box around the target
+    fn brute_force(target: &Target) -> Vec<Vec2> {
+        let bound = 40;
+        let mut velocities = Vec::new();
+
+        for vy in -bound..=bound {
+            for vx in -bound..=bound {
+                if check_hit(target.min, target.max, vx, vy).is_some() {
+                    velocities.push(Vec2::new(vx, vy));
+                }
+            }
+        }
+
+        velocities
+    }
+
+    fn target(min: (i64, i64), max: (i64, i64)) -> Target {
+        Target {
+            min: Vec2::new(min.0, min.1),
+            max: Vec2::new(max.0, max.1),
+        }
+    }
+
     #[test]
     fn test_parse() {
         let test_data = get_test_input();
+
+        assert_eq!(test_data, target((20, -10), (30, -5)));
+    }
+
+    #[test]
+    fn test_example() {
+        let velocities = valid_velocities(&get_test_input()).unwrap();
+
+        assert_eq!(velocities.len(), 112);
+        assert_eq!(velocities.iter().map(|vel| max_height(*vel)).max(), Some(45));
+        assert!(velocities.contains(&Vec2::new(6, 9)));
+        assert!(velocities.contains(&Vec2::new(30, -10)));
+    }
+
+    #[test]
+    fn test_matches_simulation() {
+        let targets = [
+            get_test_input(),
+            // Above the launcher
+            target((5, 3), (12, 8)),
+            // To the left
+            target((-30, -10), (-20, -5)),
+            // Directly overhead
+            target((-2, 4), (3, 6)),
+            // Either side of the launcher
+            target((-3, -8), (3, -4)),
+            // Level with the launcher but never stopping inside
+            target((4, -2), (5, 2)),
+            target((-12, -3), (-11, 0)),
+        ];
+
+        for target in targets {
+            let mut velocities = valid_velocities(&target).unwrap();
+            let mut expected = brute_force(&target);
+
+            velocities.sort_by_key(|vel| (vel[0], vel[1]));
+            expected.sort_by_key(|vel| (vel[0], vel[1]));
+
+            assert_eq!(velocities, expected, "{:?}", target);
+        }
+    }
+
+    #[test]
+    fn test_level_with_launcher() {
+        // x can stop at 6 inside the target and any shot up comes back through 0
+        assert_eq!(valid_velocities(&target((5, -2), (7, 2))), None);
+        assert_eq!(valid_velocities(&target((-1, 0), (1, 0))), None);
     }
 }
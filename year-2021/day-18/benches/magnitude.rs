@@ -0,0 +1,24 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use day_18::{flat::SnailfishNumber, parse_pair_str, Pair};
+
+fn get_test_input() -> &'static str {
+    include_str!("../test_input.txt")
+}
+
+fn best_pair_magnitude(c: &mut Criterion) {
+    let pairs: Vec<Pair> = get_test_input().lines().map(parse_pair_str).collect();
+    let numbers: Vec<SnailfishNumber> = get_test_input()
+        .lines()
+        .map(|line| line.parse().unwrap())
+        .collect();
+
+    c.bench_function("best_pair_magnitude_tree", |b| {
+        b.iter(|| day_18::best_pair_magnitude(black_box(&pairs)).0)
+    });
+    c.bench_function("best_pair_magnitude_flat", |b| {
+        b.iter(|| day_18::flat::best_pair_magnitude(black_box(&numbers)).0)
+    });
+}
+
+criterion_group!(benches, best_pair_magnitude);
+criterion_main!(benches);
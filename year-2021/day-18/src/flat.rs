@@ -0,0 +1,449 @@
+//! Snailfish numbers stored as a flat list of their regular numbers in order,
+//! each tagged with how many pairs it is nested inside. Exploding looks for
+//! the first two neighbours in the list at the same depth past four pairs,
+//! which is always a pair of regular numbers since sums never nest more than
+//! five pairs deep, and adds them to the numbers on either side of them in the
+//! list. The magnitude and the puzzle notation are worked out by walking the
+//! list and grouping numbers by depth.
+
+use std::{
+    fmt,
+    iter::Sum,
+    ops::Add,
+    str::FromStr,
+};
+
+use crate::{Pair, PairItem};
+
+/// Pairs nested inside this many pairs explode
+const EXPLODE_DEPTH: u8 = 4;
+
+/// Regular numbers at least this large split
+const SPLIT_THRESHOLD: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Regular {
+    value: u32,
+    /// Number of pairs this regular number is inside of
+    depth: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnailfishNumber {
+    regulars: Vec<Regular>,
+}
+
+impl SnailfishNumber {
+    fn push_item(&mut self, item: &PairItem, depth: u8) {
+        match item {
+            PairItem::Num(n) => self.regulars.push(Regular {
+                value: *n as u32,
+                depth,
+            }),
+            PairItem::Pair(p) => self.push_pair(p, depth),
+        }
+    }
+
+    fn push_pair(&mut self, pair: &Pair, depth: u8) {
+        self.push_item(&pair.0, depth + 1);
+        self.push_item(&pair.1, depth + 1);
+    }
+
+    /// Explode the leftmost pair nested inside four pairs. Returns None if
+    /// nothing exploded or Some(false) if a neighbour would have overflowed.
+    fn explode(&mut self) -> Option<bool> {
+        let idx = self.regulars.windows(2).position(|w| {
+            w[0].depth > EXPLODE_DEPTH && w[0].depth == w[1].depth
+        })?;
+
+        let Regular { value: left, depth } = self.regulars[idx];
+        let right = self.regulars[idx + 1].value;
+
+        if idx > 0 {
+            let prev = &mut self.regulars[idx - 1].value;
+
+            match prev.checked_add(left) {
+                Some(v) => *prev = v,
+                None => return Some(false),
+            }
+        }
+
+        if let Some(next) = self.regulars.get_mut(idx + 2) {
+            match next.value.checked_add(right) {
+                Some(v) => next.value = v,
+                None => return Some(false),
+            }
+        }
+
+        self.regulars[idx] = Regular {
+            value: 0,
+            depth: depth - 1,
+        };
+        self.regulars.remove(idx + 1);
+
+        Some(true)
+    }
+
+    /// Split the leftmost regular number of 10 or greater
+    fn split(&mut self) -> bool {
+        let idx = match self.regulars.iter().position(|r| r.value >= SPLIT_THRESHOLD) {
+            Some(idx) => idx,
+            None => return false,
+        };
+
+        let Regular { value, depth } = self.regulars[idx];
+
+        self.regulars[idx] = Regular {
+            value: value / 2,
+            depth: depth + 1,
+        };
+        self.regulars.insert(
+            idx + 1,
+            Regular {
+                value: value - value / 2,
+                depth: depth + 1,
+            },
+        );
+
+        true
+    }
+
+    /// Reduce until no more explosions or splits apply, None if a value overflowed
+    fn checked_reduce(mut self) -> Option<Self> {
+        loop {
+            match self.explode() {
+                Some(true) => continue,
+                Some(false) => return None,
+                None => {}
+            }
+
+            if !self.split() {
+                return Some(self);
+            }
+        }
+    }
+
+    /// Add two snailfish numbers and reduce the result. Returns None if a
+    /// regular number overflows or either side is nested too deeply to be
+    /// reduced by the explode rule (more than four pairs deep).
+    pub fn checked_add(&self, rhs: &SnailfishNumber) -> Option<SnailfishNumber> {
+        let regulars: Vec<Regular> = self
+            .regulars
+            .iter()
+            .chain(rhs.regulars.iter())
+            .map(|r| Regular {
+                value: r.value,
+                depth: r.depth + 1,
+            })
+            .collect();
+
+        if regulars.iter().any(|r| r.depth > EXPLODE_DEPTH + 1) {
+            return None;
+        }
+
+        SnailfishNumber { regulars }.checked_reduce()
+    }
+
+    pub fn magnitude(&self) -> u64 {
+        // Stack of (magnitude, depth), siblings are merged as soon as both are known
+        let mut stack: Vec<(u64, u8)> = Vec::new();
+
+        for r in self.regulars.iter() {
+            let mut top = (r.value as u64, r.depth);
+
+            while let Some(&(left, depth)) = stack.last() {
+                if depth != top.1 {
+                    break;
+                }
+
+                stack.pop();
+                top = (3 * left + 2 * top.0, depth - 1);
+            }
+
+            stack.push(top);
+        }
+
+        stack.first().map(|(m, _)| *m).unwrap_or(0)
+    }
+
+    fn fmt_depth(&self, f: &mut fmt::Formatter<'_>, next: &mut usize, depth: u8) -> fmt::Result {
+        let r = self.regulars[*next];
+
+        if r.depth == depth {
+            *next += 1;
+            write!(f, "{}", r.value)
+        } else {
+            write!(f, "[")?;
+            self.fmt_depth(f, next, depth + 1)?;
+            write!(f, ",")?;
+            self.fmt_depth(f, next, depth + 1)?;
+            write!(f, "]")
+        }
+    }
+}
+
+impl From<&Pair> for SnailfishNumber {
+    fn from(pair: &Pair) -> Self {
+        let mut number = SnailfishNumber {
+            regulars: Vec::new(),
+        };
+        number.push_pair(pair, 0);
+        number
+    }
+}
+
+/// Formats in the puzzle notation e.g. `[[1,2],3]`
+impl fmt::Display for SnailfishNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut next = 0;
+        self.fmt_depth(f, &mut next, 0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Byte offset of the unexpected character or the end of the input
+    pub pos: usize,
+    pub expected: &'static str,
+    pub found: Option<char>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.found {
+            Some(ch) => write!(f, "expected {} at {} but found `{}`", self.expected, self.pos, ch),
+            None => write!(f, "expected {} at {} but found end of input", self.expected, self.pos),
+        }
+    }
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+    regulars: Vec<Regular>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.s[self.pos..].chars().next()
+    }
+
+    fn error(&self, expected: &'static str) -> ParseError {
+        ParseError {
+            pos: self.pos,
+            expected,
+            found: self.peek(),
+        }
+    }
+
+    fn expect(&mut self, ch: char, expected: &'static str) -> Result<(), ParseError> {
+        if self.peek() != Some(ch) {
+            return Err(self.error(expected));
+        }
+
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn parse_item(&mut self, depth: u8) -> Result<(), ParseError> {
+        match self.peek() {
+            Some('[') => self.parse_pair(depth),
+            Some(ch) if ch.is_ascii_digit() => {
+                let start = self.pos;
+
+                while matches!(self.peek(), Some(ch) if ch.is_ascii_digit()) {
+                    self.pos += 1;
+                }
+
+                let value = self.s[start..self.pos].parse().map_err(|_| ParseError {
+                    pos: start,
+                    expected: "a number that fits in 32 bits",
+                    found: self.s[start..].chars().next(),
+                })?;
+
+                self.regulars.push(Regular { value, depth });
+                Ok(())
+            }
+            _ => Err(self.error("`[` or a digit")),
+        }
+    }
+
+    fn parse_pair(&mut self, depth: u8) -> Result<(), ParseError> {
+        if depth == u8::MAX {
+            return Err(self.error("less deeply nested pairs"));
+        }
+
+        self.expect('[', "`[`")?;
+        self.parse_item(depth + 1)?;
+        self.expect(',', "`,`")?;
+        self.parse_item(depth + 1)?;
+        self.expect(']', "`]`")
+    }
+}
+
+impl FromStr for SnailfishNumber {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            s: s.trim_end(),
+            pos: 0,
+            regulars: Vec::new(),
+        };
+
+        parser.parse_pair(0)?;
+
+        if parser.pos != parser.s.len() {
+            return Err(parser.error("end of input"));
+        }
+
+        Ok(SnailfishNumber {
+            regulars: parser.regulars,
+        })
+    }
+}
+
+impl Add for SnailfishNumber {
+    type Output = SnailfishNumber;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        &self + &rhs
+    }
+}
+
+impl<'a> Add<&'a SnailfishNumber> for &'a SnailfishNumber {
+    type Output = SnailfishNumber;
+
+    fn add(self, rhs: &'a SnailfishNumber) -> Self::Output {
+        self.checked_add(rhs)
+            .unwrap_or_else(|| panic!("Cannot add {} and {}", self, rhs))
+    }
+}
+
+/// Panics on an empty iterator since snailfish addition has no identity
+impl Sum for SnailfishNumber {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.reduce(|a, b| a + b)
+            .expect("Cannot sum zero snailfish numbers")
+    }
+}
+
+impl<'a> Sum<&'a SnailfishNumber> for SnailfishNumber {
+    fn sum<I: Iterator<Item = &'a SnailfishNumber>>(mut iter: I) -> Self {
+        let first = iter.next().expect("Cannot sum zero snailfish numbers").clone();
+        iter.fold(first, |a, b| &a + b)
+    }
+}
+
+/// Largest magnitude from adding two different numbers in either order
+pub fn best_pair_magnitude(numbers: &[SnailfishNumber]) -> (u64, &SnailfishNumber, &SnailfishNumber) {
+    (0..numbers.len())
+        .flat_map(|a| (0..numbers.len()).map(move |b| (a, b)))
+        .filter(|(a, b)| a != b)
+        .map(|(a, b)| ((&numbers[a] + &numbers[b]).magnitude(), &numbers[a], &numbers[b]))
+        .max_by_key(|(m, _, _)| *m)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{best_pair_magnitude, ParseError, SnailfishNumber};
+    use crate::{self as tree, parse_pair_str};
+
+    fn parse(s: &str) -> SnailfishNumber {
+        s.parse().unwrap()
+    }
+
+    const TEST_INPUT: &str = include_str!("../test_input.txt");
+
+    fn get_test_input() -> Vec<SnailfishNumber> {
+        TEST_INPUT.lines().map(parse).collect()
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for line in TEST_INPUT.lines() {
+            assert_eq!(parse(line).to_string(), line);
+        }
+    }
+
+    #[test]
+    fn test_from_pair() {
+        let s = "[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]";
+
+        assert_eq!(SnailfishNumber::from(&parse_pair_str(s)), parse(s));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            "[1,2".parse::<SnailfishNumber>(),
+            Err(ParseError { pos: 4, expected: "`]`", found: None })
+        );
+        assert_eq!(
+            "[1;2]".parse::<SnailfishNumber>(),
+            Err(ParseError { pos: 2, expected: "`,`", found: Some(';') })
+        );
+        assert_eq!(
+            "[[1,2],x]".parse::<SnailfishNumber>(),
+            Err(ParseError { pos: 7, expected: "`[` or a digit", found: Some('x') })
+        );
+        assert_eq!(
+            "[1,2]]".parse::<SnailfishNumber>(),
+            Err(ParseError { pos: 5, expected: "end of input", found: Some(']') })
+        );
+    }
+
+    #[test]
+    fn test_add() {
+        let actual = parse("[[[[4,3],4],4],[7,[[8,4],9]]]") + parse("[1,1]");
+
+        assert_eq!(actual.to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+    }
+
+    #[test]
+    fn test_sum() {
+        let numbers = ["[1,1]", "[2,2]", "[3,3]", "[4,4]", "[5,5]", "[6,6]"];
+        let total: SnailfishNumber = numbers.iter().map(|s| parse(s)).sum();
+
+        assert_eq!(total.to_string(), "[[[[5,0],[7,4]],[5,5]],[6,6]]");
+    }
+
+    #[test]
+    fn test_sum_test_input() {
+        let total: SnailfishNumber = get_test_input().iter().sum();
+
+        assert_eq!(
+            total.to_string(),
+            "[[[[6,6],[7,6]],[[7,7],[7,0]]],[[[7,7],[7,7]],[[7,8],[9,9]]]]"
+        );
+        assert_eq!(total.magnitude(), 4140);
+    }
+
+    #[test]
+    fn test_magnitude() {
+        assert_eq!(parse("[[1,2],[[3,4],5]]").magnitude(), 143);
+        assert_eq!(
+            parse("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]").magnitude(),
+            3488
+        );
+    }
+
+    #[test]
+    fn test_checked_add() {
+        let too_deep = parse("[[[[[1,1],1],1],1],1]");
+        assert_eq!(too_deep.checked_add(&parse("[1,1]")), None);
+
+        let huge = parse("[[[[4294967295,1],1],1],[[[1,1],1],1]]");
+        assert_eq!(parse("[[[[1,1],1],1],1]").checked_add(&huge), None);
+    }
+
+    #[test]
+    fn test_best_pair_magnitude_matches_tree() {
+        let numbers = get_test_input();
+        let pairs: Vec<tree::Pair> = TEST_INPUT.lines().map(parse_pair_str).collect();
+
+        assert_eq!(best_pair_magnitude(&numbers).0, 3993);
+        assert_eq!(tree::best_pair_magnitude(&pairs).0, 3993);
+    }
+}
@@ -0,0 +1,442 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use ndarray::prelude::*;
+
+use crate::Scanner;
+
+type Point = [i32; 3];
+type Rotation = [[i32; 3]; 3];
+
+#[derive(Debug, Clone)]
+pub struct AlignConfig {
+    /// Number of beacons two scanners must have in common to be aligned
+    pub min_overlap: usize,
+    /// How many beacons may be missing from a scanner's report. This lowers the
+    /// overlap needed and allows that many beacons inside both scanners' range
+    /// to be seen by only one of them.
+    pub max_missing: usize,
+    /// How far a scanner can see along each axis, alignments that would put a
+    /// beacon in range of a scanner that didn't report it are rejected
+    pub detection_range: Option<i32>,
+}
+
+impl Default for AlignConfig {
+    fn default() -> Self {
+        AlignConfig {
+            min_overlap: 12,
+            max_missing: 0,
+            detection_range: Some(1000),
+        }
+    }
+}
+
+/// Maps points from one scanner's coordinates to another's as `p.dot(rotation) + offset`.
+/// The offset is also the position of the scanner in the other's coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScannerTransform {
+    pub rotation: Array2<i32>,
+    pub offset: Array1<i32>,
+}
+
+impl ScannerTransform {
+    pub fn apply(&self, beacons: &Scanner) -> Scanner {
+        beacons.dot(&self.rotation) + &self.offset
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Alignment {
+    /// Scanners that could be aligned with each other, the first scanner of each is its root
+    pub components: Vec<Vec<usize>>,
+    /// Index into components for each scanner
+    pub component_of: Vec<usize>,
+    /// Transform from each scanner's coordinates to its component root's coordinates
+    pub transforms: Vec<ScannerTransform>,
+}
+
+impl Alignment {
+    pub fn is_connected(&self) -> bool {
+        self.components.len() <= 1
+    }
+
+    /// Transform into scanner 0's coordinates if scanner is reachable from it
+    pub fn relative_to_first(&self, scanner: usize) -> Option<&ScannerTransform> {
+        if self.component_of[scanner] == self.component_of[0] {
+            Some(&self.transforms[scanner])
+        } else {
+            None
+        }
+    }
+
+    /// Every distinct beacon seen by a component in its root's coordinates
+    pub fn beacons(&self, input: &[Scanner], component: usize) -> HashSet<Array1<i32>> {
+        self.components[component]
+            .iter()
+            .flat_map(|scanner| {
+                self.transforms[*scanner]
+                    .apply(&input[*scanner])
+                    .rows()
+                    .into_iter()
+                    .map(|row| row.to_owned())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Largest manhattan distance between two scanners in a component
+    pub fn max_scanner_distance(&self, component: usize) -> i32 {
+        let scanners = &self.components[component];
+        let mut farthest = 0;
+
+        for a in scanners.iter() {
+            for b in scanners.iter() {
+                let dist = (&self.transforms[*a].offset - &self.transforms[*b].offset)
+                    .mapv(i32::abs)
+                    .sum();
+
+                farthest = i32::max(dist, farthest);
+            }
+        }
+
+        farthest
+    }
+}
+
+fn determinant(r: &Rotation) -> i32 {
+    r[0][0] * (r[1][1] * r[2][2] - r[1][2] * r[2][1])
+        - r[0][1] * (r[1][0] * r[2][2] - r[1][2] * r[2][0])
+        + r[0][2] * (r[1][0] * r[2][1] - r[1][1] * r[2][0])
+}
+
+/// The 24 ways a scanner can be facing, reflections are excluded
+fn rotations() -> Vec<Rotation> {
+    let mut rotations = Vec::new();
+
+    for x_col in 0..3 {
+        for y_col in 0..3 {
+            for z_col in 0..3 {
+                if x_col == y_col || y_col == z_col || x_col == z_col {
+                    continue;
+                }
+
+                for signs in 0..8 {
+                    let sign = |bit: i32| if signs & (1 << bit) != 0 { -1 } else { 1 };
+
+                    let mut rot = [[0; 3]; 3];
+                    rot[x_col][0] = sign(0);
+                    rot[y_col][1] = sign(1);
+                    rot[z_col][2] = sign(2);
+
+                    if determinant(&rot) == 1 {
+                        rotations.push(rot);
+                    }
+                }
+            }
+        }
+    }
+
+    rotations
+}
+
+fn rotate(p: &Point, r: &Rotation) -> Point {
+    let mut out = [0; 3];
+
+    for (j, o) in out.iter_mut().enumerate() {
+        *o = (0..3).map(|i| p[i] * r[i][j]).sum();
+    }
+
+    out
+}
+
+fn in_range(p: &Point, center: &Point, range: i32) -> bool {
+    (0..3).all(|i| (p[i] - center[i]).abs() <= range)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Transform {
+    rotation: Rotation,
+    offset: Point,
+}
+
+impl Transform {
+    fn identity() -> Self {
+        Transform {
+            rotation: [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+            offset: [0; 3],
+        }
+    }
+
+    fn apply(&self, p: &Point) -> Point {
+        let r = rotate(p, &self.rotation);
+        [r[0] + self.offset[0], r[1] + self.offset[1], r[2] + self.offset[2]]
+    }
+
+    /// Apply self then other
+    fn then(&self, other: &Transform) -> Transform {
+        let mut rotation = [[0; 3]; 3];
+
+        for (i, row) in rotation.iter_mut().enumerate() {
+            *row = rotate(&self.rotation[i], &other.rotation);
+        }
+
+        Transform {
+            rotation,
+            offset: other.apply(&self.offset),
+        }
+    }
+
+    fn to_scanner_transform(self) -> ScannerTransform {
+        ScannerTransform {
+            rotation: Array2::from_shape_fn((3, 3), |(i, j)| self.rotation[i][j]),
+            offset: Array1::from(self.offset.to_vec()),
+        }
+    }
+}
+
+/// Sum of squared distances between each pair of beacons, these don't change with rotation
+fn pair_distances(beacons: &[Point]) -> HashMap<i32, usize> {
+    let mut dists = HashMap::new();
+
+    for (i, a) in beacons.iter().enumerate() {
+        for b in beacons[..i].iter() {
+            let d = (0..3).map(|c| (a[c] - b[c]).pow(2)).sum();
+            *dists.entry(d).or_insert(0) += 1;
+        }
+    }
+
+    dists
+}
+
+struct ScannerInfo {
+    beacons: Vec<Point>,
+    beacon_set: HashSet<Point>,
+    distances: HashMap<i32, usize>,
+}
+
+impl ScannerInfo {
+    fn new(scanner: &Scanner) -> Self {
+        let beacons: Vec<Point> = scanner
+            .rows()
+            .into_iter()
+            .map(|row| [row[0], row[1], row[2]])
+            .collect();
+
+        ScannerInfo {
+            beacon_set: beacons.iter().copied().collect(),
+            distances: pair_distances(&beacons),
+            beacons,
+        }
+    }
+}
+
+/// Count beacons in range of both scanners that only one of them reported
+fn count_missing(a: &ScannerInfo, b_in_a: &[Point], b_position: &Point, range: i32) -> usize {
+    let b_set: HashSet<Point> = b_in_a.iter().copied().collect();
+
+    let missing_from_a = b_in_a
+        .iter()
+        .filter(|p| in_range(p, &[0; 3], range) && !a.beacon_set.contains(*p))
+        .count();
+
+    let missing_from_b = a
+        .beacons
+        .iter()
+        .filter(|p| in_range(p, b_position, range) && !b_set.contains(*p))
+        .count();
+
+    missing_from_a + missing_from_b
+}
+
+/// Find the transform from b's coordinates to a's if they overlap enough
+fn align_pair(a: &ScannerInfo, b: &ScannerInfo, config: &AlignConfig, rotations: &[Rotation]) -> Option<Transform> {
+    let required = config.min_overlap.saturating_sub(config.max_missing).max(1);
+
+    // Cheap check before trying every rotation, beacons in common are all the
+    // same distance apart in both scanners
+    let common_distances: usize = a
+        .distances
+        .iter()
+        .map(|(d, count)| usize::min(*count, b.distances.get(d).copied().unwrap_or(0)))
+        .sum();
+
+    if common_distances < required * (required - 1) / 2 {
+        return None;
+    }
+
+    for rotation in rotations {
+        let rotated: Vec<Point> = b.beacons.iter().map(|p| rotate(p, rotation)).collect();
+        let mut votes: HashMap<Point, usize> = HashMap::new();
+
+        for pa in a.beacons.iter() {
+            for pb in rotated.iter() {
+                let offset = [pa[0] - pb[0], pa[1] - pb[1], pa[2] - pb[2]];
+                *votes.entry(offset).or_insert(0) += 1;
+            }
+        }
+
+        for (offset, count) in votes {
+            if count < required {
+                continue;
+            }
+
+            let transform = Transform {
+                rotation: *rotation,
+                offset,
+            };
+
+            if let Some(range) = config.detection_range {
+                let b_in_a: Vec<Point> = b.beacons.iter().map(|p| transform.apply(p)).collect();
+
+                if count_missing(a, &b_in_a, &offset, range) > config.max_missing {
+                    continue;
+                }
+            }
+
+            return Some(transform);
+        }
+    }
+
+    None
+}
+
+/// Align every scanner to the lowest numbered scanner it can be reached from
+pub fn align_scanners(input: &[Scanner], config: &AlignConfig) -> Alignment {
+    let infos: Vec<ScannerInfo> = input.iter().map(ScannerInfo::new).collect();
+    let rotations = rotations();
+
+    let mut transforms: Vec<Option<Transform>> = vec![None; input.len()];
+    let mut component_of = vec![0; input.len()];
+    let mut components = Vec::new();
+
+    for root in 0..input.len() {
+        if transforms[root].is_some() {
+            continue;
+        }
+
+        let component_idx = components.len();
+        let mut component = vec![root];
+        let mut queue = VecDeque::from([root]);
+
+        transforms[root] = Some(Transform::identity());
+        component_of[root] = component_idx;
+
+        while let Some(a) = queue.pop_front() {
+            let a_to_root = transforms[a].unwrap();
+
+            for b in 0..input.len() {
+                if transforms[b].is_some() {
+                    continue;
+                }
+
+                if let Some(b_to_a) = align_pair(&infos[a], &infos[b], config, &rotations) {
+                    transforms[b] = Some(b_to_a.then(&a_to_root));
+                    component_of[b] = component_idx;
+                    component.push(b);
+                    queue.push_back(b);
+                }
+            }
+        }
+
+        component.sort_unstable();
+        components.push(component);
+    }
+
+    Alignment {
+        components,
+        component_of,
+        transforms: transforms
+            .into_iter()
+            .map(|t| t.unwrap().to_scanner_transform())
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ndarray::prelude::*;
+
+    use super::{align_scanners, AlignConfig};
+    use crate::test::get_test_input;
+
+    #[test]
+    fn test_align_test_input() {
+        let input = get_test_input();
+        let alignment = align_scanners(&input, &AlignConfig::default());
+
+        assert!(alignment.is_connected());
+        assert_eq!(alignment.beacons(&input, 0).len(), 79);
+        assert_eq!(alignment.max_scanner_distance(0), 3621);
+
+        let offsets: Vec<Array1<i32>> = (0..input.len())
+            .map(|s| alignment.relative_to_first(s).unwrap().offset.clone())
+            .collect();
+
+        assert_eq!(
+            offsets,
+            vec![
+                array![0, 0, 0],
+                array![68, -1246, -43],
+                array![1105, -1205, 1229],
+                array![-92, -2380, -20],
+                array![-20, -1133, 1061],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_transform_matches_scanner_0() {
+        let input = get_test_input();
+        let alignment = align_scanners(&input, &AlignConfig::default());
+
+        // Worked example: scanner 1 sees -618,-824,-621 at 686,422,578
+        let t = alignment.relative_to_first(1).unwrap();
+        let beacon = t.apply(&array![[686, 422, 578]]);
+
+        assert_eq!(beacon, array![[-618, -824, -621]]);
+    }
+
+    #[test]
+    fn test_disconnected() {
+        let input = get_test_input();
+        // Scanner 2 only overlaps with scanner 4 so it is on its own here
+        let subset = vec![input[0].clone(), input[1].clone(), input[2].clone()];
+
+        let alignment = align_scanners(&subset, &AlignConfig::default());
+
+        assert!(!alignment.is_connected());
+        assert_eq!(alignment.components, vec![vec![0, 1], vec![2]]);
+        assert!(alignment.relative_to_first(2).is_none());
+        assert_eq!(alignment.transforms[2].offset, array![0, 0, 0]);
+    }
+
+    #[test]
+    fn test_missing_beacon() {
+        let input = get_test_input();
+        let alignment = align_scanners(&input, &AlignConfig::default());
+        let t = alignment.relative_to_first(1).unwrap();
+
+        // Drop one of the 12 beacons scanner 1 shares with scanner 0
+        let scanner_0: Vec<Array1<i32>> = input[0].rows().into_iter().map(|r| r.to_owned()).collect();
+        let in_0 = t.apply(&input[1]);
+        let shared = in_0
+            .rows()
+            .into_iter()
+            .position(|r| scanner_0.contains(&r.to_owned()))
+            .unwrap();
+        let kept: Vec<usize> = (0..input[1].nrows()).filter(|r| *r != shared).collect();
+        let subset = vec![input[0].clone(), input[1].select(Axis(0), &kept)];
+
+        let strict = align_scanners(&subset, &AlignConfig::default());
+        assert!(!strict.is_connected());
+
+        let tolerant = align_scanners(
+            &subset,
+            &AlignConfig {
+                max_missing: 1,
+                ..AlignConfig::default()
+            },
+        );
+        assert!(tolerant.is_connected());
+        assert_eq!(tolerant.transforms[1], *t);
+    }
+}
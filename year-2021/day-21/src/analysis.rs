@@ -0,0 +1,225 @@
+use std::fmt::Write;
+
+use rustc_hash::FxHashMap as HashMap;
+
+use crate::{DiceRules, Game};
+
+/// How a single starting position plays out when every roll is equally likely
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameReport {
+    /// 0-based start position of each player
+    pub start: Vec<u32>,
+    /// finish_turns[t][p] is the probability player p wins on turn t + 1,
+    /// every player's move counts as one turn
+    pub finish_turns: Vec<Vec<f64>>,
+}
+
+impl GameReport {
+    pub fn win_probability(&self, player_num: usize) -> f64 {
+        self.finish_turns.iter().map(|wins| wins[player_num]).sum()
+    }
+
+    /// Probability the game ends on each turn regardless of who wins
+    pub fn finish_distribution(&self) -> Vec<f64> {
+        self.finish_turns.iter().map(|wins| wins.iter().sum()).collect()
+    }
+
+    pub fn expected_turns(&self) -> f64 {
+        self.finish_distribution()
+            .iter()
+            .enumerate()
+            .map(|(turn, p)| (turn + 1) as f64 * p)
+            .sum()
+    }
+}
+
+/// Follow the probability of every game state forward one turn at a time
+pub fn analyze(rules: &DiceRules, game: Game) -> GameReport {
+    rules.check_game(&game);
+
+    let start = game.player_pos.clone();
+    let player_count = start.len();
+
+    let universes_per_turn = (rules.die_faces as f64).powi(rules.rolls_per_turn as i32);
+    let roll_probabilities: Vec<(u32, f64)> = rules
+        .roll_frequencies()
+        .into_iter()
+        .map(|(total, ways)| (total, ways as f64 / universes_per_turn))
+        .collect();
+
+    let mut finish_turns = Vec::new();
+    let mut states: HashMap<Game, f64> = HashMap::default();
+    states.insert(game, 1.0);
+
+    while !states.is_empty() {
+        let mut wins = vec![0.0; player_count];
+        let mut next_states: HashMap<Game, f64> = HashMap::default();
+
+        for (game, p) in states {
+            for (total, roll_p) in roll_probabilities.iter().copied() {
+                let mut game_fork = game.clone();
+                let player_num = game_fork.cur_player;
+
+                if game_fork.take_turn(rules, total) {
+                    wins[player_num] += p * roll_p;
+                } else {
+                    *next_states.entry(game_fork).or_insert(0.0) += p * roll_p;
+                }
+            }
+        }
+
+        finish_turns.push(wins);
+        states = next_states;
+    }
+
+    GameReport { start, finish_turns }
+}
+
+/// Analyze every combination of start positions, the first player's position changes slowest
+pub fn analyze_all_starts(rules: &DiceRules) -> Vec<GameReport> {
+    let mut starts: Vec<Vec<u32>> = vec![Vec::new()];
+
+    for _ in 0..rules.player_count {
+        starts = starts
+            .into_iter()
+            .flat_map(|start| {
+                (0..rules.board_size).map(move |pos| {
+                    let mut start = start.clone();
+                    start.push(pos);
+                    start
+                })
+            })
+            .collect();
+    }
+
+    starts
+        .into_iter()
+        .map(|start| {
+            let game = Game {
+                cur_player: 0,
+                player_score: vec![0; start.len()],
+                player_pos: start,
+            };
+
+            analyze(rules, game)
+        })
+        .collect()
+}
+
+/// One row per report with 1-based start positions, win probabilities, expected
+/// turns and then the probability of finishing on each turn
+pub fn to_csv(reports: &[GameReport]) -> String {
+    let player_count = reports.first().map(|r| r.start.len()).unwrap_or(0);
+    let max_turns = reports.iter().map(|r| r.finish_turns.len()).max().unwrap_or(0);
+
+    let mut header: Vec<String> = Vec::new();
+    header.extend((1..=player_count).map(|p| format!("p{}_start", p)));
+    header.extend((1..=player_count).map(|p| format!("p{}_win", p)));
+    header.push("expected_turns".to_string());
+    header.extend((1..=max_turns).map(|t| format!("turn_{}", t)));
+
+    let mut out = header.join(",");
+    out.push('\n');
+
+    for report in reports {
+        let mut row: Vec<String> = Vec::new();
+        row.extend(report.start.iter().map(|pos| (pos + 1).to_string()));
+        row.extend((0..player_count).map(|p| report.win_probability(p).to_string()));
+        row.push(report.expected_turns().to_string());
+
+        let distribution = report.finish_distribution();
+        row.extend((0..max_turns).map(|t| distribution.get(t).copied().unwrap_or(0.0).to_string()));
+
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Player 1's win percentage with player 1's start down the side and player 2's across the top
+pub fn win_matrix(rules: &DiceRules, reports: &[GameReport]) -> String {
+    assert_eq!(rules.player_count, 2, "Win matrix needs exactly two players");
+
+    let mut out = String::new();
+
+    write!(out, "p1\\p2").unwrap();
+    for p2_start in 1..=rules.board_size {
+        write!(out, " {:>5}", p2_start).unwrap();
+    }
+    out.push('\n');
+
+    for p1_start in 0..rules.board_size {
+        write!(out, "{:>5}", p1_start + 1).unwrap();
+
+        for p2_start in 0..rules.board_size {
+            let report = reports
+                .iter()
+                .find(|r| r.start == [p1_start, p2_start])
+                .expect("Report for every start");
+
+            write!(out, " {:>5.1}", report.win_probability(0) * 100.0).unwrap();
+        }
+
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::{analyze, analyze_all_starts, to_csv, win_matrix};
+    use crate::{play_deterministic, DiceRules, Game};
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_probabilities_sum_to_one() {
+        let report = analyze(&DiceRules::DIRAC, Game::new([3, 7]));
+
+        assert_close(report.win_probability(0) + report.win_probability(1), 1.0);
+        assert_close(report.finish_distribution().iter().sum(), 1.0);
+        assert!(report.expected_turns() > 2.0);
+    }
+
+    #[test]
+    fn test_single_face_die_matches_deterministic_game() {
+        let rules = DiceRules {
+            die_faces: 1,
+            rolls_per_turn: 1,
+            target_score: 50,
+            ..DiceRules::DIRAC
+        };
+
+        let outcome = play_deterministic(&rules, Game::new([3, 7]));
+        let report = analyze(&rules, Game::new([3, 7]));
+
+        assert_close(report.win_probability(outcome.winner), 1.0);
+        assert_close(report.expected_turns(), outcome.rolls as f64);
+    }
+
+    #[test]
+    fn test_reports() {
+        let rules = DiceRules {
+            board_size: 3,
+            target_score: 5,
+            ..DiceRules::DIRAC
+        };
+
+        let reports = analyze_all_starts(&rules);
+        assert_eq!(reports.len(), 9);
+
+        let csv = to_csv(&reports);
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("p1_start,p2_start,p1_win,p2_win,expected_turns,turn_1,"));
+        assert!(lines.next().unwrap().starts_with("1,1,"));
+        assert_eq!(lines.count(), 8);
+
+        let matrix = win_matrix(&rules, &reports);
+        assert_eq!(matrix.lines().count(), 4);
+        assert!(matrix.starts_with("p1\\p2     1     2     3\n"));
+    }
+}
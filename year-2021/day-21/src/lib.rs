@@ -1,128 +1,320 @@
-use std::hash::Hash;
+pub mod analysis;
 
 // Substantially faster
 use rustc_hash::FxHashMap as HashMap;
 
-use itertools::iproduct;
-
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub struct Game {
-    /// 0 = player 1, 1 = player 2
-    cur_player: u8,
-    player_score: [u8; 2],
-    /// 0-based (0-9) instead of 1-10
-    player_pos: [u8; 2],
+/// Everything that can change about a game of Dirac Dice
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct DiceRules {
+    /// Spaces on the circular board, positions are 0-based
+    pub board_size: u32,
+    /// Score a player needs to reach to win
+    pub target_score: u32,
+    /// The die rolls 1 to die_faces
+    pub die_faces: u32,
+    pub rolls_per_turn: u32,
+    pub player_count: usize,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
-struct GameKey {
-    player_score: [u8; 2],
-    /// 0-based (0-9) instead of 1-10
-    player_pos: [u8; 2],
-}
+impl DiceRules {
+    /// Part 1 rules with the deterministic 100-sided die
+    pub const DETERMINISTIC: DiceRules = DiceRules {
+        board_size: 10,
+        target_score: 1000,
+        die_faces: 100,
+        rolls_per_turn: 3,
+        player_count: 2,
+    };
 
-#[derive(Default)]
-struct Memoizer {
-    table: HashMap<Game, [usize; 2]>
-}
+    /// Part 2 rules with the 3-sided Dirac die
+    pub const DIRAC: DiceRules = DiceRules {
+        board_size: 10,
+        target_score: 21,
+        die_faces: 3,
+        rolls_per_turn: 3,
+        player_count: 2,
+    };
 
-impl Memoizer {
-    fn get(&self, game: &Game) -> Option<[usize; 2]> {
-        // One player does not have any intrinsic advantage over the other beyond the state of Game.
-        // This means we can swap players and see if we have a memoized result then swap the result.
-        self.table
-            .get(game)
-            .copied()
-            .or_else(|| self.table.get(&game.flipped()).map(|scores| flip_arr(*scores)))
-    }
+    /// Every total a turn's rolls can add up to along with the number of
+    /// universes that roll it
+    pub fn roll_frequencies(&self) -> Vec<(u32, u128)> {
+        let mut frequencies: Vec<u128> = vec![1];
 
-    fn insert(&mut self, game: Game, player_wins: [usize; 2]) {
-        self.table.insert(game, player_wins);
-    }
-}
+        for _ in 0..self.rolls_per_turn {
+            let mut next = vec![0; frequencies.len() + self.die_faces as usize];
+
+            for (total, ways) in frequencies.iter().enumerate() {
+                for face in 1..=self.die_faces as usize {
+                    next[total + face] += ways;
+                }
+            }
 
-// Probably not worth the perf since it only shaves off ~0.5ms.
-impl Hash for Game {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        // The derive(Hash) likely does a state.write for each field but we most of these fields use
-        // fewer bits than that
+            frequencies = next;
+        }
+
+        frequencies
+            .into_iter()
+            .enumerate()
+            .filter(|(_, ways)| *ways > 0)
+            .map(|(total, ways)| (total as u32, ways))
+            .collect()
+    }
 
-        let hash: u32 = self.cur_player as u32      // cur_player is 1-bit
-            | (self.player_pos[0] as u32) << 1      // position is 4-bit since it is mod 10
-            | (self.player_pos[1] as u32) << 5      // position is 4-bit since it is mod 10
-            | (self.player_score[0] as u32) << 9    // score is 5-bit since it is < 21
-            | (self.player_score[1] as u32) << 14; // score is 5-bit since it is < 21
-        state.write_u32(hash);
+    fn check_game(&self, game: &Game) {
+        assert_eq!(game.player_pos.len(), self.player_count, "Wrong number of players");
+        assert!(game.player_pos.iter().all(|pos| *pos < self.board_size), "Player off the board");
     }
 }
 
-fn flip_arr<T: Copy>(a: [T; 2]) -> [T; 2] {
-    [a[1], a[0]]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Game {
+    /// Index of the player whose turn it is
+    cur_player: usize,
+    player_score: Vec<u32>,
+    /// 0-based (0-9 on the standard board) instead of 1-10
+    player_pos: Vec<u32>,
 }
 
 impl Game {
-    pub fn new(player_pos: [i32; 2]) -> Self {
+    pub fn new<const N: usize>(player_pos: [i32; N]) -> Self {
         Game {
             cur_player: 0,
-            player_pos: [player_pos[0] as u8, player_pos[1] as u8],
-            player_score: [0, 0],
+            player_pos: player_pos.iter().map(|pos| *pos as u32).collect(),
+            player_score: vec![0; N],
         }
     }
 
-    fn flipped(&self) -> Self {
+    pub fn scores(&self) -> &[u32] {
+        &self.player_score
+    }
+
+    /// Move the current player forward and pass the turn on, returns true if they won
+    fn take_turn(&mut self, rules: &DiceRules, total: u32) -> bool {
+        let player_num = self.cur_player;
+
+        self.player_pos[player_num] = (self.player_pos[player_num] + total) % rules.board_size;
+        self.player_score[player_num] += self.player_pos[player_num] + 1;
+
+        if self.player_score[player_num] >= rules.target_score {
+            return true;
+        }
+
+        self.cur_player = (self.cur_player + 1) % self.player_pos.len();
+
+        false
+    }
+
+    /// The same game with the players renumbered so the current player is player 0
+    fn normalized(&self) -> Self {
+        let mut player_score = self.player_score.clone();
+        let mut player_pos = self.player_pos.clone();
+
+        player_score.rotate_left(self.cur_player);
+        player_pos.rotate_left(self.cur_player);
+
         Game {
-            cur_player: 1 - self.cur_player,
-            player_score: flip_arr(self.player_score),
-            player_pos: flip_arr(self.player_pos),
+            cur_player: 0,
+            player_score,
+            player_pos,
+        }
+    }
+}
+
+/// How the deterministic game ended
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DeterministicOutcome {
+    pub winner: usize,
+    pub scores: Vec<u32>,
+    pub rolls: u32,
+}
+
+impl DeterministicOutcome {
+    /// Lowest score multiplied by the number of times the die was rolled
+    pub fn loser_product(&self) -> u64 {
+        let losing_score = self.scores.iter().copied().min().unwrap_or(0);
+        losing_score as u64 * self.rolls as u64
+    }
+}
+
+/// Play with a die that rolls 1, 2, 3... wrapping back to 1 after die_faces
+pub fn play_deterministic(rules: &DiceRules, mut game: Game) -> DeterministicOutcome {
+    rules.check_game(&game);
+
+    let mut dice = (0..rules.die_faces).cycle().map(|roll| roll + 1);
+    let mut rolls = 0;
+
+    loop {
+        let player_num = game.cur_player;
+        let total: u32 = dice.by_ref().take(rules.rolls_per_turn as usize).sum();
+        rolls += rules.rolls_per_turn;
+
+        if game.take_turn(rules, total) {
+            return DeterministicOutcome {
+                winner: player_num,
+                scores: game.player_score,
+                rolls,
+            };
         }
     }
 }
 
-fn play_part2_inner(
+#[derive(Default)]
+struct Memoizer {
+    table: HashMap<Game, Vec<u128>>,
+}
+
+impl Memoizer {
+    // No player has any intrinsic advantage over another beyond the state of Game.
+    // This means games are only stored with the current player first and results are
+    // renumbered to match.
+    fn get(&self, game: &Game) -> Option<&Vec<u128>> {
+        self.table.get(game)
+    }
+
+    fn insert(&mut self, game: Game, player_wins: Vec<u128>) {
+        self.table.insert(game, player_wins);
+    }
+}
+
+/// Wins for each player starting from a normalized game
+fn count_wins_inner(
+    rules: &DiceRules,
+    roll_frequencies: &[(u32, u128)],
     game: Game,
-    possible_rolls: &Vec<u8>,
     memoize: &mut Memoizer,
-) -> [usize; 2] {
-    assert!(game.player_score[0] < 21);
-    assert!(game.player_score[1] < 21);
-
+) -> Vec<u128> {
     if let Some(cached) = memoize.get(&game) {
-        return cached;
+        return cached.clone();
     }
 
-    let mut player_wins = [0; 2];
+    let player_count = game.player_pos.len();
+    let mut player_wins = vec![0; player_count];
 
-    let player_num = game.cur_player as usize;
-
-    for roll in possible_rolls.iter().copied() {
+    for (total, ways) in roll_frequencies.iter().copied() {
         let mut game_fork = game.clone();
 
-        game_fork.player_pos[player_num] = (game_fork.player_pos[player_num] + roll) % 10;
-        game_fork.player_score[player_num] += game_fork.player_pos[player_num] + 1;
-
-        if game_fork.player_score[player_num] >= 21 {
-            player_wins[player_num] += 1;
+        if game_fork.take_turn(rules, total) {
+            player_wins[0] += ways;
         } else {
-            game_fork.cur_player = 1 - game_fork.cur_player;
-            let all_outcomes = play_part2_inner(game_fork, possible_rolls, memoize);
+            // Player n in the forked game is player n + 1 here
+            let all_outcomes = count_wins_inner(rules, roll_frequencies, game_fork.normalized(), memoize);
 
-            for player_num in 0..2 {
-                player_wins[player_num] += all_outcomes[player_num];
+            for (player_num, wins) in all_outcomes.into_iter().enumerate() {
+                player_wins[(player_num + 1) % player_count] += ways * wins;
             }
         }
     }
 
-    memoize.insert(game, player_wins);
+    memoize.insert(game, player_wins.clone());
 
     player_wins
 }
 
-pub fn play_part2(game: Game) -> [usize; 2] {
-    // It might speed things up to record the number of occurrences with each possible roll
-    // then multiply the wins by occurrences (though memoization already removes most of the gain)
-    let possible_rolls_part2: Vec<u8> = iproduct!(1..4, 1..4, 1..4)
-        .map(|(a, b, c)| a + b + c)
-        .collect();
+/// Number of universes each player wins in when every roll splits the universe
+/// once for each face of the die
+pub fn count_wins(rules: &DiceRules, game: Game) -> Vec<u128> {
+    rules.check_game(&game);
+
+    let roll_frequencies = rules.roll_frequencies();
     let mut memoized = Memoizer::default();
-    play_part2_inner(game, &possible_rolls_part2, &mut memoized)
+
+    let mut player_wins = count_wins_inner(rules, &roll_frequencies, game.normalized(), &mut memoized);
+    player_wins.rotate_right(game.cur_player);
+
+    player_wins
+}
+
+pub fn play_part2(game: Game) -> Vec<u128> {
+    count_wins(&DiceRules::DIRAC, game)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{count_wins, play_deterministic, DiceRules, Game};
+
+    /// Count wins by following every single roll without any memoization
+    fn count_wins_brute_force(rules: &DiceRules, game: Game, rolls_left: u32, total: u32) -> Vec<u128> {
+        let mut player_wins = vec![0; rules.player_count];
+
+        for face in 1..=rules.die_faces {
+            if rolls_left > 1 {
+                let wins = count_wins_brute_force(rules, game.clone(), rolls_left - 1, total + face);
+                player_wins.iter_mut().zip(wins).for_each(|(a, b)| *a += b);
+                continue;
+            }
+
+            let mut game_fork = game.clone();
+            let player_num = game_fork.cur_player;
+
+            if game_fork.take_turn(rules, total + face) {
+                player_wins[player_num] += 1;
+            } else {
+                let wins = count_wins_brute_force(rules, game_fork, rules.rolls_per_turn, 0);
+                player_wins.iter_mut().zip(wins).for_each(|(a, b)| *a += b);
+            }
+        }
+
+        player_wins
+    }
+
+    #[test]
+    fn test_roll_frequencies() {
+        assert_eq!(
+            DiceRules::DIRAC.roll_frequencies(),
+            vec![(3, 1), (4, 3), (5, 6), (6, 7), (7, 6), (8, 3), (9, 1)]
+        );
+    }
+
+    #[test]
+    fn test_part1() {
+        let outcome = play_deterministic(&DiceRules::DETERMINISTIC, Game::new([3, 7]));
+
+        assert_eq!(outcome.winner, 0);
+        assert_eq!(outcome.rolls, 993);
+        assert_eq!(outcome.loser_product(), 739785);
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let rules_list = [
+            DiceRules {
+                target_score: 8,
+                ..DiceRules::DIRAC
+            },
+            DiceRules {
+                board_size: 7,
+                target_score: 10,
+                die_faces: 2,
+                rolls_per_turn: 2,
+                player_count: 3,
+            },
+        ];
+
+        for rules in rules_list {
+            let start = if rules.player_count == 2 {
+                Game::new([3, 7])
+            } else {
+                Game::new([0, 3, 6])
+            };
+
+            assert_eq!(
+                count_wins(&rules, start.clone()),
+                count_wins_brute_force(&rules, start, rules.rolls_per_turn, 0),
+                "{:?}",
+                rules
+            );
+        }
+    }
+
+    #[test]
+    fn test_large_target_does_not_overflow() {
+        let rules = DiceRules {
+            target_score: 30,
+            ..DiceRules::DIRAC
+        };
+
+        let wins = count_wins(&rules, Game::new([3, 7]));
+
+        assert!(wins.iter().sum::<u128>() > u64::MAX as u128);
+    }
 }
@@ -1,53 +1,32 @@
-use std::collections::HashMap;
+use std::env;
 
-use itertools::iproduct;
-
-use day_21::{Game, play_part2};
+use day_21::analysis::{analyze_all_starts, to_csv, win_matrix};
+use day_21::{count_wins, play_deterministic, DiceRules, Game};
 
 fn main() {
+    // `analysis` prints win probabilities for every start instead of the puzzle answers
+    if env::args().nth(1).as_deref() == Some("analysis") {
+        let reports = analyze_all_starts(&DiceRules::DIRAC);
+
+        println!("{}", win_matrix(&DiceRules::DIRAC, &reports));
+        print!("{}", to_csv(&reports));
+
+        return;
+    }
+
     let start_positions = [
         6,
         2
     ];
 
-    let mut player_score: [i32; 2] = [0; 2];
-    let mut player_pos: [i32; 2] = start_positions;
-
-    let mut rolls = 0;
-
-
-    let mut maybe_winning_player = None;
-
-    {
-        let mut dice = (0..).map(|roll| {
-            rolls += 1;
-            (roll % 100) + 1
-        });
-
-        'game: loop {
-            for player_num in 0..2 {
-                let total:i32  = dice.by_ref().take(3).sum();
-                //dbg!(total);
-                player_pos[player_num] = (player_pos[player_num] + total) % 10;
-                player_score[player_num] += player_pos[player_num] + 1;
-                //dbg!(player_score[player_num]);
-
-                if player_score[player_num] >= 1000 {
-                    maybe_winning_player = Some(player_num);
-                    break 'game;
-                }
-            }
-        }
-    }
+    let outcome = play_deterministic(&DiceRules::DETERMINISTIC, Game::new(start_positions));
 
-    dbg!(player_score, rolls);
+    dbg!(&outcome);
 
-    println!("{}", rolls*player_score[1-maybe_winning_player.unwrap()]);
+    println!("{}", outcome.loser_product());
 
     dbg!(start_positions);
-    let win_counts = play_part2(
-        Game::new(start_positions)
-    );
+    let win_counts = count_wins(&DiceRules::DIRAC, Game::new(start_positions));
 
     dbg!(win_counts);
 }
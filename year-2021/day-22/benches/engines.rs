@@ -0,0 +1,53 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use day_22::engine::{CoordinateCompression, InclusionExclusion, KDTreeEngine, VolumeEngine};
+use day_22::{Command, Cuboid, Vec3};
+
+/// Commands shaped like the puzzle input: big overlapping cuboids, mostly on
+fn generate_commands(count: usize, seed: u64) -> Vec<Command> {
+    let mut state = seed;
+    let mut next = move |range: i32| {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % range as u64) as i32
+    };
+
+    (0..count)
+        .map(|_| {
+            let min = Vec3::new(next(200_000), next(200_000), next(200_000)) - Vec3::repeat(100_000);
+            let shape = Vec3::new(next(40_000), next(40_000), next(40_000)) + Vec3::repeat(1);
+
+            Command {
+                on: next(4) != 0,
+                cuboid: Cuboid {
+                    min,
+                    max: min + shape,
+                },
+            }
+        })
+        .collect()
+}
+
+fn engines(c: &mut Criterion) {
+    let engines: [&dyn VolumeEngine; 3] = [&KDTreeEngine, &InclusionExclusion, &CoordinateCompression];
+    let mut group = c.benchmark_group("lit_volume");
+    // Coordinate compression takes seconds an iteration at the larger sizes
+    group.sample_size(10);
+
+    // The puzzle input has 420 commands
+    for count in [100, 420, 840] {
+        let commands = generate_commands(count, 2021);
+
+        for engine in engines {
+            group.bench_with_input(BenchmarkId::new(engine.name(), count), &commands, |b, commands| {
+                b.iter(|| engine.lit_volume(black_box(commands.clone())))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, engines);
+criterion_main!(benches);
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use crate::kdtree::KDTree;
+use crate::{Command, Cuboid};
+
+/// A way of working out how many cubes are lit after running every command
+pub trait VolumeEngine {
+    fn name(&self) -> &'static str;
+
+    fn lit_volume(&self, commands: Vec<Command>) -> usize;
+}
+
+/// Splits cuboids into disjoint pieces stored in a KD-tree
+pub struct KDTreeEngine;
+
+impl VolumeEngine for KDTreeEngine {
+    fn name(&self) -> &'static str {
+        "kd-tree"
+    }
+
+    fn lit_volume(&self, commands: Vec<Command>) -> usize {
+        let mut kdtree = KDTree::new();
+        kdtree.extend(commands);
+        kdtree.get_volume()
+    }
+}
+
+/// Keeps a count for every cuboid it has seen, each command cancels out its
+/// overlap with what came before by adding the overlap with the opposite sign
+pub struct InclusionExclusion;
+
+impl VolumeEngine for InclusionExclusion {
+    fn name(&self) -> &'static str {
+        "inclusion-exclusion"
+    }
+
+    fn lit_volume(&self, commands: Vec<Command>) -> usize {
+        let mut signed_cuboids: HashMap<Cuboid, i64> = HashMap::new();
+
+        for cmd in commands {
+            let mut updates: HashMap<Cuboid, i64> = HashMap::new();
+
+            for (cuboid, sign) in signed_cuboids.iter() {
+                if let Some(overlap) = cuboid.intersection(&cmd.cuboid) {
+                    *updates.entry(overlap).or_insert(0) -= sign;
+                }
+            }
+
+            if cmd.on && cmd.cuboid.get_volume() > 0 {
+                *updates.entry(cmd.cuboid).or_insert(0) += 1;
+            }
+
+            for (cuboid, sign) in updates {
+                let count = signed_cuboids.entry(cuboid).or_insert(0);
+                *count += sign;
+            }
+
+            signed_cuboids.retain(|_, sign| *sign != 0);
+        }
+
+        let total: i64 = signed_cuboids
+            .iter()
+            .map(|(cuboid, sign)| cuboid.get_volume() as i64 * sign)
+            .sum();
+
+        total as usize
+    }
+}
+
+/// Maps every distinct cuboid edge to a grid index so each grid cell is a box
+/// of cubes that are all lit or all unlit. Works through one x slab at a time
+/// so only a y by z grid is held in memory.
+pub struct CoordinateCompression;
+
+fn axis_coords(commands: &[Command], axis: usize) -> Vec<i32> {
+    let mut coords: Vec<i32> = commands
+        .iter()
+        .flat_map(|cmd| [cmd.cuboid.min[axis], cmd.cuboid.max[axis]].into_iter())
+        .collect();
+    coords.sort_unstable();
+    coords.dedup();
+    coords
+}
+
+fn coord_index(coords: &[i32], value: i32) -> usize {
+    coords.binary_search(&value).expect("Coordinate was compressed")
+}
+
+impl VolumeEngine for CoordinateCompression {
+    fn name(&self) -> &'static str {
+        "coordinate compression"
+    }
+
+    fn lit_volume(&self, commands: Vec<Command>) -> usize {
+        let xs = axis_coords(&commands, 0);
+        let ys = axis_coords(&commands, 1);
+        let zs = axis_coords(&commands, 2);
+
+        let cells_y = ys.len().saturating_sub(1);
+        let cells_z = zs.len().saturating_sub(1);
+
+        let mut total = 0;
+        let mut slab = vec![false; cells_y * cells_z];
+
+        for x_range in xs.windows(2) {
+            slab.iter_mut().for_each(|cell| *cell = false);
+
+            for cmd in commands
+                .iter()
+                .filter(|cmd| cmd.cuboid.min.x <= x_range[0] && x_range[0] < cmd.cuboid.max.x)
+            {
+                let y_cells = coord_index(&ys, cmd.cuboid.min.y)..coord_index(&ys, cmd.cuboid.max.y);
+                let z_cells = coord_index(&zs, cmd.cuboid.min.z)..coord_index(&zs, cmd.cuboid.max.z);
+
+                for y in y_cells {
+                    for cell in &mut slab[y * cells_z + z_cells.start..y * cells_z + z_cells.end] {
+                        *cell = cmd.on;
+                    }
+                }
+            }
+
+            let mut slab_area = 0;
+
+            for (n, lit) in slab.iter().enumerate() {
+                if *lit {
+                    let (y, z) = (n / cells_z, n % cells_z);
+                    slab_area += (ys[y + 1] - ys[y]) as usize * (zs[z + 1] - zs[z]) as usize;
+                }
+            }
+
+            total += slab_area * (x_range[1] - x_range[0]) as usize;
+        }
+
+        total
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use proptest::prelude::*;
+
+    use super::{CoordinateCompression, InclusionExclusion, KDTreeEngine, VolumeEngine};
+    use crate::fixtures::command;
+    use crate::{parse_input, Input};
+
+    const ENGINES: [&dyn VolumeEngine; 3] = [&KDTreeEngine, &InclusionExclusion, &CoordinateCompression];
+
+    fn get_test_input() -> Input {
+        let test_data_str = include_str!("../test_input3.txt");
+
+        let test_data_reader = Cursor::new(test_data_str.to_owned());
+
+        parse_input(test_data_reader)
+    }
+
+    #[test]
+    fn test_reboot_example() {
+        for engine in ENGINES {
+            assert_eq!(engine.lit_volume(get_test_input()), 2758514936282235, "{}", engine.name());
+        }
+    }
+
+    #[test]
+    fn test_no_commands() {
+        for engine in ENGINES {
+            assert_eq!(engine.lit_volume(Vec::new()), 0, "{}", engine.name());
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_engines_agree(commands in prop::collection::vec(command(), 1..20)) {
+            let expected = KDTreeEngine.lit_volume(commands.clone());
+
+            prop_assert_eq!(InclusionExclusion.lit_volume(commands.clone()), expected);
+            prop_assert_eq!(CoordinateCompression.lit_volume(commands), expected);
+        }
+    }
+}
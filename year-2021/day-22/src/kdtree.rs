@@ -0,0 +1,423 @@
+use std::mem;
+
+use crate::{split_cubes, AAPlane, Command, Cuboid, Vec3};
+
+/// Leaves holding more cuboids than this are split on insert
+const LEAF_SIZE: usize = 5;
+
+enum KDTreeNode {
+    Branch {
+        split: AAPlane,
+        left: Box<KDTreeNode>,
+        right: Box<KDTreeNode>,
+    },
+    Leaf(Vec<Cuboid>),
+}
+
+impl KDTreeNode {
+    fn insert(&mut self, cmd: Command, parent_split_axis: Option<usize>) {
+        match self {
+            KDTreeNode::Leaf(cubes) => {
+                let cubes_to_split = mem::take(cubes);
+
+                *cubes = cubes_to_split
+                    .into_iter()
+                    .flat_map(|on_cuboid| {
+                        let (outside, _inside) = split_cubes(cmd.cuboid.clone(), on_cuboid);
+                        outside.into_iter()
+                    })
+                    .collect();
+
+                if cmd.on && cmd.cuboid.get_volume() > 0 {
+                    cubes.push(cmd.cuboid);
+                }
+
+                self.balance(parent_split_axis);
+            }
+            KDTreeNode::Branch { split, left, right } => {
+                let on = cmd.on;
+                let axis = split.axis();
+                let (maybe_split_left, maybe_split_right) = split.split(cmd.cuboid);
+
+                if let Some(split_left) = maybe_split_left.filter(|c| c.get_volume() > 0) {
+                    left.insert(
+                        Command {
+                            on,
+                            cuboid: split_left,
+                        },
+                        Some(axis),
+                    );
+                }
+
+                if let Some(split_right) = maybe_split_right.filter(|c| c.get_volume() > 0) {
+                    right.insert(
+                        Command {
+                            on,
+                            cuboid: split_right,
+                        },
+                        Some(axis),
+                    );
+                }
+
+                self.collapse();
+                self.rebuild_if_unbalanced(parent_split_axis);
+            }
+        }
+    }
+
+    /// Rebuild a branch from scratch when one side holds more than three
+    /// quarters of its cuboids, like a scapegoat tree
+    fn rebuild_if_unbalanced(&mut self, parent_split_axis: Option<usize>) {
+        let (left_len, right_len) = match self {
+            KDTreeNode::Branch { left, right, .. } => (left.len(), right.len()),
+            KDTreeNode::Leaf(_) => return,
+        };
+
+        let total = left_len + right_len;
+
+        if total <= LEAF_SIZE * 2 || usize::max(left_len, right_len) * 4 <= total * 3 {
+            return;
+        }
+
+        let mut cubes = Vec::with_capacity(total);
+        self.drain_into(&mut cubes);
+
+        *self = KDTreeNode::Leaf(cubes);
+        self.balance(parent_split_axis);
+    }
+
+    fn drain_into(&mut self, out: &mut Vec<Cuboid>) {
+        match self {
+            KDTreeNode::Leaf(cubes) => out.append(cubes),
+            KDTreeNode::Branch { left, right, .. } => {
+                left.drain_into(out);
+                right.drain_into(out);
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            KDTreeNode::Leaf(cubes) => cubes.len(),
+            KDTreeNode::Branch { left, right, .. } => left.len() + right.len(),
+        }
+    }
+
+    /// Split an oversized leaf at the median cuboid edge, trying the axis after
+    /// the parent's first. Splits that would leave a side with every cuboid are
+    /// skipped since they only duplicate cuboids that straddle the plane.
+    fn balance(&mut self, parent_split_axis: Option<usize>) {
+        let nodes = match self {
+            KDTreeNode::Leaf(nodes) if nodes.len() > LEAF_SIZE => nodes,
+            _ => return,
+        };
+
+        let first_axis = parent_split_axis.map(|a| (a + 1) % 3).unwrap_or(0);
+
+        for split_axis in (0..3).map(|n| (first_axis + n) % 3) {
+            let mut partition_candidates: Vec<i32> = nodes
+                .iter()
+                .flat_map(|cube| [cube.min[split_axis], cube.max[split_axis]].into_iter())
+                .collect();
+            partition_candidates.sort_unstable();
+
+            let median = partition_candidates[partition_candidates.len() / 2];
+
+            let split = AAPlane::from_axis_num(split_axis, median);
+            let (left, right): (Vec<Cuboid>, Vec<Cuboid>) = {
+                let (left, right) = split.split_multiple(nodes.iter().cloned());
+                (
+                    left.into_iter().filter(|c| c.get_volume() > 0).collect(),
+                    right.into_iter().filter(|c| c.get_volume() > 0).collect(),
+                )
+            };
+
+            if left.is_empty() || right.is_empty() || left.len() >= nodes.len() || right.len() >= nodes.len() {
+                continue;
+            }
+
+            let mut left = KDTreeNode::Leaf(left);
+            let mut right = KDTreeNode::Leaf(right);
+
+            left.balance(Some(split_axis));
+            right.balance(Some(split_axis));
+
+            *self = KDTreeNode::Branch {
+                split,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+
+            return;
+        }
+    }
+
+    /// Merge a branch back into a leaf once its children are small enough
+    fn collapse(&mut self) {
+        let merged = match self {
+            KDTreeNode::Branch { left, right, .. } => match (left.as_mut(), right.as_mut()) {
+                (KDTreeNode::Leaf(left), KDTreeNode::Leaf(right))
+                    if left.len() + right.len() <= LEAF_SIZE =>
+                {
+                    let mut merged = mem::take(left);
+                    merged.append(right);
+                    merged
+                }
+                _ => return,
+            },
+            KDTreeNode::Leaf(_) => return,
+        };
+
+        *self = KDTreeNode::Leaf(merged);
+    }
+
+    fn contains(&self, point: Vec3) -> bool {
+        match self {
+            KDTreeNode::Leaf(cubes) => cubes.iter().any(|cube| cube.contains(point)),
+            KDTreeNode::Branch { split, left, right } => {
+                if point[split.axis()] < split.split {
+                    left.contains(point)
+                } else {
+                    right.contains(point)
+                }
+            }
+        }
+    }
+
+    fn volume_within(&self, region: &Cuboid) -> usize {
+        match self {
+            KDTreeNode::Leaf(cubes) => cubes
+                .iter()
+                .filter_map(|cube| cube.intersection(region))
+                .map(|cube| cube.get_volume())
+                .sum(),
+            KDTreeNode::Branch { split, left, right } => {
+                let axis = split.axis();
+                let mut total = 0;
+
+                if region.min[axis] < split.split {
+                    total += left.volume_within(region);
+                }
+
+                if region.max[axis] > split.split {
+                    total += right.volume_within(region);
+                }
+
+                total
+            }
+        }
+    }
+
+    fn get_volume(&self) -> usize {
+        match self {
+            KDTreeNode::Leaf(cubes) => cubes.iter().map(Cuboid::get_volume).sum(),
+            KDTreeNode::Branch { left, right, .. } => left.get_volume() + right.get_volume(),
+        }
+    }
+
+    fn depth(&self) -> usize {
+        match self {
+            KDTreeNode::Leaf(_) => 1,
+            KDTreeNode::Branch { left, right, .. } => 1 + usize::max(left.depth(), right.depth()),
+        }
+    }
+}
+
+/// Set of lit cubes stored as disjoint cuboids partitioned by a KD-tree
+pub struct KDTree(KDTreeNode);
+
+impl KDTree {
+    pub fn new() -> Self {
+        KDTree(KDTreeNode::Leaf(Vec::new()))
+    }
+
+    /// Turn every cube in the command's cuboid on or off
+    pub fn insert(&mut self, cmd: Command) {
+        self.0.insert(cmd, None);
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        self.0.contains(point)
+    }
+
+    /// Number of lit cubes inside region
+    pub fn volume_within(&self, region: &Cuboid) -> usize {
+        self.0.volume_within(region)
+    }
+
+    pub fn get_volume(&self) -> usize {
+        self.0.get_volume()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.0.depth()
+    }
+
+    /// Every lit cuboid, none of them overlap
+    pub fn cuboids(&self) -> Cuboids<'_> {
+        Cuboids {
+            stack: vec![&self.0],
+            leaf: [].iter(),
+        }
+    }
+}
+
+impl Default for KDTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extend<Command> for KDTree {
+    fn extend<T: IntoIterator<Item = Command>>(&mut self, iter: T) {
+        for cmd in iter {
+            self.insert(cmd);
+        }
+    }
+}
+
+pub struct Cuboids<'a> {
+    stack: Vec<&'a KDTreeNode>,
+    leaf: std::slice::Iter<'a, Cuboid>,
+}
+
+impl<'a> Iterator for Cuboids<'a> {
+    type Item = &'a Cuboid;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(cube) = self.leaf.next() {
+                return Some(cube);
+            }
+
+            match self.stack.pop()? {
+                KDTreeNode::Leaf(cubes) => self.leaf = cubes.iter(),
+                KDTreeNode::Branch { left, right, .. } => {
+                    self.stack.push(right);
+                    self.stack.push(left);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use std::io::Cursor;
+
+    use super::KDTree;
+    use crate::fixtures::command;
+    use crate::{count_on_voxels, parse_input, Command, Cuboid, Vec3};
+
+    fn build(commands: &[Command]) -> KDTree {
+        let mut kdtree = KDTree::new();
+        kdtree.extend(commands.iter().cloned());
+        kdtree
+    }
+
+    fn init_region() -> Cuboid {
+        Cuboid::from_str("x=-50..50,y=-50..50,z=-50..50")
+    }
+
+    #[test]
+    fn test_larger_example() {
+        let commands = parse_input(Cursor::new(include_str!("../test_input2.txt")));
+
+        assert_eq!(build(&commands).volume_within(&init_region()), 590784);
+    }
+
+    #[test]
+    fn test_reboot_example() {
+        let commands = parse_input(Cursor::new(include_str!("../test_input3.txt")));
+        let kdtree = build(&commands);
+
+        assert_eq!(kdtree.volume_within(&init_region()), 474140);
+        assert_eq!(kdtree.get_volume(), 2758514936282235);
+    }
+
+    #[test]
+    fn test_rebalances_on_insert() {
+        let mut kdtree = KDTree::new();
+
+        for n in 0..64 {
+            kdtree.insert(Command {
+                on: true,
+                cuboid: Cuboid {
+                    min: Vec3::new(n * 2, 0, 0),
+                    max: Vec3::new(n * 2 + 1, 1, 1),
+                },
+            });
+        }
+
+        assert_eq!(kdtree.get_volume(), 64);
+        assert!(kdtree.depth() < 16, "depth {}", kdtree.depth());
+    }
+
+    #[test]
+    fn test_off_collapses() {
+        let mut kdtree = KDTree::new();
+
+        for n in 0..16 {
+            kdtree.insert(Command::from_str(&format!("on x={0}..{0},y=0..0,z=0..0", n * 2)));
+        }
+
+        kdtree.insert(Command::from_str("off x=0..40,y=0..0,z=0..0"));
+
+        assert_eq!(kdtree.get_volume(), 0);
+        assert_eq!(kdtree.depth(), 1);
+    }
+
+    #[test]
+    fn test_volume_within() {
+        let kdtree = build(&[
+            Command::from_str("on x=-60..-40,y=0..0,z=0..0"),
+            Command::from_str("on x=40..60,y=0..0,z=0..0"),
+        ]);
+
+        assert_eq!(kdtree.volume_within(&Cuboid::from_str("x=-50..50,y=-50..50,z=-50..50")), 22);
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_voxels(commands in prop::collection::vec(command(), 1..12)) {
+            let kdtree = build(&commands);
+            let region = Cuboid::from_str("x=-6..10,y=-6..10,z=-6..10");
+
+            let voxels = count_on_voxels(&commands, &region);
+
+            prop_assert_eq!(kdtree.get_volume(), voxels);
+            prop_assert_eq!(kdtree.volume_within(&region), voxels);
+        }
+
+        #[test]
+        fn test_contains_matches_last_command(commands in prop::collection::vec(command(), 1..12), point in prop::array::uniform3(-6i32..11)) {
+            let kdtree = build(&commands);
+            let point = Vec3::from(point);
+
+            let expected = commands
+                .iter()
+                .rev()
+                .find(|cmd| cmd.cuboid.contains(point))
+                .map(|cmd| cmd.on)
+                .unwrap_or(false);
+
+            prop_assert_eq!(kdtree.contains(point), expected);
+        }
+
+        #[test]
+        fn test_cuboids_are_disjoint(commands in prop::collection::vec(command(), 1..12)) {
+            let kdtree = build(&commands);
+            let cuboids: Vec<&Cuboid> = kdtree.cuboids().collect();
+
+            for (n, a) in cuboids.iter().enumerate() {
+                for b in cuboids[n + 1..].iter() {
+                    prop_assert_eq!(a.intersection(b), None);
+                }
+            }
+
+            prop_assert_eq!(cuboids.iter().map(|c| c.get_volume()).sum::<usize>(), kdtree.get_volume());
+        }
+    }
+}
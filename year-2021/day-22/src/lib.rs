@@ -0,0 +1,444 @@
+use std::io::BufRead;
+
+pub mod engine;
+pub mod kdtree;
+
+#[cfg(test)]
+use kdtree::KDTree;
+
+pub type Vec3 = nalgebra::Vector3<i32>;
+use building_blocks::core::prelude::*;
+use building_blocks::storage::{prelude::*, ChunkHashMap, ChunkMap2x1};
+pub type Input = Vec<Command>;
+
+pub fn parse_input(reader: impl BufRead) -> Input {
+    reader
+        .lines()
+        .map(|line| Command::from_str(line.unwrap().as_str()))
+        .collect()
+}
+
+/// Half open box of cubes, max is one past the last cube on each axis
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Cuboid {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Cuboid {
+    fn from_str(s: &str) -> Self {
+        let mut components = s.split(",").map(|c| {
+            let (_, r) = c.split_once("=").unwrap();
+
+            let (min, max) = r.split_once("..").unwrap();
+            (
+                i32::from_str_radix(min, 10).unwrap(),
+                i32::from_str_radix(max, 10).unwrap(),
+            )
+        });
+
+        let x_r = components.next().unwrap();
+        let y_r = components.next().unwrap();
+        let z_r = components.next().unwrap();
+
+        Cuboid {
+            min: Vec3::new(x_r.0, y_r.0, z_r.0),
+            max: Vec3::new(x_r.1 + 1, y_r.1 + 1, z_r.1 + 1),
+        }
+    }
+
+    fn to_extent(&self) -> Extent3i {
+        Extent3i::from_min_and_shape(to_grid_point(self.min), to_grid_point(self.max - self.min))
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        (0..3).all(|axis| self.min[axis] <= point[axis] && point[axis] < self.max[axis])
+    }
+
+    /// The overlapping part of both cuboids if there is any
+    pub fn intersection(&self, other: &Cuboid) -> Option<Cuboid> {
+        let min = self.min.sup(&other.min);
+        let max = self.max.inf(&other.max);
+
+        if (0..3).all(|axis| min[axis] < max[axis]) {
+            Some(Cuboid { min, max })
+        } else {
+            None
+        }
+    }
+
+    pub fn get_volume(&self) -> usize {
+        let delta = (self.max - self.min).abs();
+        (delta[0] as usize) * (delta[1] as usize) * (delta[2] as usize)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Command {
+    pub on: bool,
+    pub cuboid: Cuboid,
+}
+
+impl Command {
+    fn from_str(s: &str) -> Self {
+        let (on_off_str, cuboid_str) = s.split_once(" ").unwrap();
+
+        Command {
+            on: match on_off_str {
+                "on" => true,
+                "off" => false,
+                other => panic!("Invalid command `{}` must start with on or off", other),
+            },
+            cuboid: Cuboid::from_str(cuboid_str),
+        }
+    }
+}
+
+fn to_grid_point(v: Vec3) -> PointN<[i32; 3]> {
+    PointN(v.data.0[0])
+}
+
+/// Undirected axis aligned plane
+#[derive(Debug)]
+struct AAPlane {
+    axis: u8,
+    split: i32
+}
+
+impl AAPlane {
+    fn from_axis_num(axis: usize, val: i32) -> Self {
+        assert!(axis <= 2);
+
+        AAPlane {
+            axis: axis as u8,
+            split: val
+        }
+    }
+
+    fn axis(&self) -> usize {
+        self.axis as usize
+    }
+}
+
+/// Axis aligned plane with a facing direction
+#[derive(Debug)]
+struct HalfSpace(bool, AAPlane);
+
+impl HalfSpace {
+    fn split(&self, cube: Cuboid) -> (Option<Cuboid>, Option<Cuboid>) {
+        let (left, right) = self.1.split(cube);
+
+        if self.0 {
+            (left, right)
+        } else {
+            (right, left)
+        }
+    }
+}
+
+impl AAPlane {
+    fn split(&self, cube: Cuboid) -> (Option<Cuboid>, Option<Cuboid>) {
+        let axis = self.axis();
+
+        if cube.min[axis] < self.split && self.split <= cube.max[axis] {
+            let mut left_max = cube.max;
+            left_max[axis] = self.split;
+
+            let mut right_min = cube.min;
+            right_min[axis] = self.split;
+
+
+            (
+                Some(Cuboid {
+                    min: cube.min,
+                    max: left_max,
+                }),
+                Some(Cuboid {
+                    min: right_min,
+                    max: cube.max,
+                }),
+            )
+        } else if cube.max[axis] < self.split {
+            (Some(cube), None)
+        } else if cube.min[axis] >= self.split {
+            (None, Some(cube))
+        } else {
+            panic!("Invalid {}-axis split: {:?} by {}", axis, cube, self.split);
+        }
+    }
+
+    fn split_multiple(&self, cubes: impl Iterator<Item = Cuboid>) -> (Vec<Cuboid>, Vec<Cuboid>) {
+        let (left, right): (Vec<Option<Cuboid>>, Vec<Option<Cuboid>>) =
+            cubes.map(|c| self.split(c)).unzip();
+
+        let output = (
+            left.into_iter().filter_map(|c| c).collect(),
+            right.into_iter().filter_map(|c| c).collect(),
+        );
+
+        output
+    }
+}
+
+/// Iterator for all the half spaces representing the sides of the cube
+fn cube_planes(cube: Cuboid) -> impl Iterator<Item = HalfSpace> {
+    (0..3).flat_map(move |dim| {
+        (0..2).map(move |side| {
+            let v = if side == 0 { cube.min } else { cube.max };
+
+            HalfSpace(
+                side == 0,
+                AAPlane::from_axis_num(dim, v[dim])
+            )
+        })
+    })
+}
+
+fn split_cubes(splitter: Cuboid, target: Cuboid) -> (Vec<Cuboid>, Vec<Cuboid>) {
+    let mut left = Vec::new();
+    let mut inside = vec![target];
+
+    for half_space in cube_planes(splitter) {
+        let mut new_inside = Vec::new();
+
+        for cur_inside_cube in inside.iter() {
+            let (maybe_outside, maybe_inside) = half_space.split(cur_inside_cube.clone());
+
+            if let Some(outside) = maybe_outside {
+                if outside.get_volume() > 0 {
+                    left.push(outside);
+                }
+            }
+
+            if let Some(inside) = maybe_inside {
+                if inside.get_volume() > 0 {
+                    new_inside.push(inside);
+                }
+            }
+        }
+
+        inside = new_inside;
+    }
+
+    (left, inside)
+}
+
+#[derive(Default)]
+struct Splits([Option<i32>; 3]);
+
+/// The -50..50 region the reactor is initialized in for part 1
+pub fn initialization_region() -> Cuboid {
+    Cuboid {
+        min: Vec3::repeat(-50),
+        max: Vec3::repeat(51),
+    }
+}
+
+/// Count lit cubes inside region by running every command on a voxel grid
+pub fn count_on_voxels(commands: &[Command], region: &Cuboid) -> usize {
+    let extent = region.to_extent();
+
+    let mut map: Array3x1<i32> = Array3x1::fill(extent, 0);
+
+    for cmd in commands.iter() {
+        let cmd_extent = cmd.cuboid.to_extent();
+
+        let val = if cmd.on { 1 } else { 0 };
+
+        map.fill_extent(&cmd_extent, val);
+    }
+
+    let mut lit_cells = 0;
+
+    map.for_each(&extent, |_p: Point3i, val: i32| {
+        if val != 0 {
+            lit_cells += 1;
+        }
+    });
+
+    lit_cells
+}
+
+/// Strategies shared by the tests of each module
+#[cfg(test)]
+mod fixtures {
+    use proptest::prelude::*;
+
+    use crate::{Command, Cuboid, Vec3};
+
+    prop_compose! {
+        fn small_cuboid()(min in prop::array::uniform3(-6i32..6), shape in prop::array::uniform3(1i32..6)) -> Cuboid {
+            let min = Vec3::from(min);
+            Cuboid {
+                min,
+                max: min + Vec3::from(shape),
+            }
+        }
+    }
+
+    prop_compose! {
+        pub fn command()(on in any::<bool>(), cuboid in small_cuboid()) -> Command {
+            Command { on, cuboid }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use crate::{
+        parse_input, split_cubes, AAPlane, Command, Cuboid, HalfSpace, Input, KDTree, Vec3,
+    };
+
+    fn get_test_input() -> Input {
+        let test_data_str = include_str!("../test_input.txt");
+
+        let test_data_reader = Cursor::new(test_data_str.to_owned());
+
+        parse_input(test_data_reader)
+    }
+
+    #[test]
+    fn test_parse() {
+        let test_data = get_test_input();
+    }
+
+    #[test]
+    fn split_cube_with_halfspace() {
+        let cube = Cuboid {
+            min: Vec3::new(-5, -5, -5),
+            max: Vec3::new(5, 5, 5),
+        };
+
+        let halfspace = HalfSpace(true, AAPlane::from_axis_num(0, 0));
+
+        let (maybe_a, maybe_b) = halfspace.split(cube);
+
+        let a = maybe_a.unwrap();
+        let b = maybe_b.unwrap();
+    }
+
+    #[test]
+    fn split_cube_with_halfspace_min() {
+        let cube = Cuboid {
+            min: Vec3::new(-5, -5, -5),
+            max: Vec3::new(5, 5, 5),
+        };
+
+        let halfspace = HalfSpace(true, AAPlane::from_axis_num(0, -5));
+
+        let (maybe_a, maybe_b) = halfspace.split(cube);
+
+        assert_eq!(maybe_a, None);
+        let b = maybe_b.unwrap();
+    }
+
+    #[test]
+    fn split_cube_with_halfspace_max() {
+        let cube = Cuboid {
+            min: Vec3::new(-5, -5, -5),
+            max: Vec3::new(5, 5, 5),
+        };
+
+        let halfspace = HalfSpace(true, AAPlane::from_axis_num(0, 6));
+
+        let (maybe_a, maybe_b) = halfspace.split(cube);
+
+        let a = maybe_a.unwrap();
+        assert_eq!(maybe_b, None);
+    }
+
+    #[test]
+    fn test_split_cubes() {
+        let cube_a = Cuboid {
+            min: Vec3::new(-5, -5, -5),
+            max: Vec3::new(5, 5, 5),
+        };
+
+        let cube_b = Cuboid {
+            min: Vec3::new(0, -5, -5),
+            max: Vec3::new(5, 5, 5),
+        };
+
+        let (cube_a_outside, cube_a_inside) = split_cubes(cube_b, cube_a);
+
+        dbg!(&cube_a_outside);
+        dbg!(&cube_a_inside);
+
+        assert_eq!(cube_a_outside.len(), 1);
+        assert_eq!(cube_a_inside.len(), 1);
+
+        assert_eq!(cube_a_outside[0].max[0], 0);
+        assert_eq!(cube_a_inside[0].min[0], 0);
+
+        assert_eq!(cube_a_outside[0].get_volume(), 5 * 10 * 10);
+        assert_eq!(cube_a_inside[0].get_volume(), 5 * 10 * 10);
+    }
+
+    #[test]
+    fn test_split_cubes_inside() {
+        let cube_a = Cuboid {
+            min: Vec3::new(0, 0, 0),
+            max: Vec3::new(3, 3, 3),
+        };
+
+        let cube_b = Cuboid {
+            min: Vec3::new(1, 1, 1),
+            max: Vec3::new(2, 2, 2),
+        };
+
+        let (cube_a_outside, cube_a_inside) = split_cubes(cube_b, cube_a);
+
+        dbg!(&cube_a_outside);
+        dbg!(&cube_a_inside);
+
+        let inside_vol: usize = cube_a_inside.iter().map(|c| c.get_volume()).sum();
+        let outside_vol: usize = cube_a_outside.iter().map(|c| c.get_volume()).sum();
+
+        assert_eq!(inside_vol, 1);
+        assert_eq!(outside_vol, 3 * 3 * 3 - 1);
+
+        //assert_eq!(cube_a_outside.len(), 1);
+        assert_eq!(cube_a_inside.len(), 1);
+    }
+
+    #[test]
+    fn test_kd_tree_single_node() {
+        let mut kdtree = KDTree::new();
+
+        let cmd = Command::from_str("on x=10..12,y=10..12,z=10..12");
+
+        kdtree.insert(cmd);
+
+        assert_eq!(kdtree.get_volume(), 3 * 3 * 3);
+    }
+
+    #[test]
+    fn test_kd_tree_two_overlapping_nodes() {
+        let mut kdtree = KDTree::new();
+
+        let cmd_1 = Command::from_str("on x=10..12,y=10..12,z=10..12");
+        let cmd_2 = Command::from_str("on x=11..13,y=11..13,z=11..13");
+
+        kdtree.insert(cmd_1);
+        kdtree.insert(cmd_2);
+
+        assert_eq!(kdtree.get_volume(), 46);
+    }
+
+    #[test]
+    fn test_kd_tree_two_overlapping_nodes_one_off() {
+        let mut kdtree = KDTree::new();
+
+        let cmd_1 = Command::from_str("on x=10..12,y=10..12,z=10..12");
+        let cmd_2 = Command::from_str("on x=11..13,y=11..13,z=11..13");
+        let cmd_3 = Command::from_str("off x=9..11,y=9..11,z=9..11");
+
+        kdtree.insert(cmd_1);
+        kdtree.insert(cmd_2);
+        kdtree.insert(cmd_3);
+
+        assert_eq!(kdtree.get_volume(), 38);
+    }
+}
@@ -2,24 +2,28 @@ use std::collections::{HashMap, HashSet, BinaryHeap};
 use std::fmt;
 use std::io::{self, BufRead};
 
+use anyhow::{bail, Result};
 use building_blocks::core::prelude::*;
-use building_blocks::storage::{prelude::*, ChunkHashMap, ChunkMap2x1};
+use building_blocks::storage::prelude::*;
 
 type Map = Array2x1<u8>;
 
 type Vec2 = nalgebra::Vector2<i32>;
-type Input = Vec<i32>;
 
-fn parse_input(mut reader: impl BufRead) -> Input {
-    unimplemented!()
-}
+/// Energy adds up fast with ten times more per letter so it gets 64 bits
+type Energy = i64;
 
-const HALLWAY_LENGTH: i32 = 11;
+/// Rows the part 2 instructions unfold into the middle of the rooms, only
+/// given for the puzzle's four rooms
+const FOLDED_LINES: [&str; 2] = ["  #D#C#B#A#", "  #D#B#A#C#"];
 
-const ROOM_X_POSITIONS: [i32; 4] = [3, 5, 7, 9];
+/// Row of the diagram the hallway is on
+const HALLWAY_Y: i32 = 1;
 
-const ROOM_Y: i32 = 2;
-const ROOM_SIZE: i32 = 4;
+/// Map tiles
+const WALL: u8 = 0;
+const HALLWAY: u8 = 1;
+const ROOM: u8 = 2;
 
 const NEIGHBORS_DIRS: [Vec2; 4] = [
     Vec2::new(0, -1),
@@ -28,46 +32,253 @@ const NEIGHBORS_DIRS: [Vec2; 4] = [
     Vec2::new(1, 0),
 ];
 
-fn hallway_positions() -> impl Iterator<Item = Vec2> {
-    (0..HALLWAY_LENGTH).map(|x| Vec2::new(x + 1, 1))
+#[derive(Debug)]
+struct Burrow {
+    map: Map,
+    hallway_xs: Vec<i32>,
+    room_xs: Vec<i32>,
+    /// Top row of every room
+    room_y: i32,
+    room_size: i32,
 }
 
-fn load_map() -> Map {
-    let extent = Extent2i::from_min_and_shape(Point2i::fill(0), Point2i::fill(16));
+impl Burrow {
+    fn hallway_positions(&self) -> impl Iterator<Item = Vec2> + '_ {
+        self.hallway_xs.iter().map(|x| Vec2::new(*x, HALLWAY_Y))
+    }
 
-    let mut map: Array2x1<u8> = Array2x1::fill(extent, 0);
+    fn room_ys(&self) -> std::ops::Range<i32> {
+        self.room_y..self.room_y + self.room_size
+    }
+
+    fn room_positions(&self) -> impl Iterator<Item = Vec2> + '_ {
+        self.room_xs
+            .iter()
+            .copied()
+            .flat_map(move |x| self.room_ys().map(move |y| Vec2::new(x, y)))
+    }
 
-    for x in 0..HALLWAY_LENGTH {
-        *map.get_mut(PointN([x + 1, 1])) = 1;
+    fn spawn_amphipods(&self, types: &[AmphipodType]) -> Vec<Amphipod> {
+        types
+            .iter()
+            .copied()
+            .zip(self.room_positions())
+            .map(|(ty, pos)| Amphipod {
+                pos,
+                ty,
+                state: State::Fresh,
+            })
+            .collect()
     }
 
-    for room_x in ROOM_X_POSITIONS {
-        for room_y in ROOM_Y..ROOM_Y + ROOM_SIZE {
-            *map.get_mut(PointN([room_x, room_y])) = 2;
+    /// Every amphipod sorted into rooms in letter order
+    fn goal(&self, pods: &[Amphipod]) -> Vec<Amphipod> {
+        let mut goal_ty: Vec<AmphipodType> = pods.iter().map(|p| p.ty).collect();
+        goal_ty.sort();
+
+        let mut goal = self.spawn_amphipods(&goal_ty);
+
+        for pod in goal.iter_mut() {
+            pod.state = State::Done;
         }
+
+        goal
     }
 
-    map
+    /// Mark amphipods that start in their own room with only their own type
+    /// below them as done, they never need to move
+    fn settle(&self, pods: &mut [Amphipod]) {
+        let goal = self.goal(pods);
+        let goal_ty_by_pos: HashMap<Vec2, AmphipodType> =
+            goal.iter().map(|pod| (pod.pos, pod.ty)).collect();
+        let pod_ty_by_pos: HashMap<Vec2, AmphipodType> =
+            pods.iter().map(|pod| (pod.pos, pod.ty)).collect();
+
+        for pod in pods.iter_mut() {
+            let home = (pod.pos.y..self.room_y + self.room_size).all(|y| {
+                let pos = Vec2::new(pod.pos.x, y);
+                pod_ty_by_pos.get(&pos) == Some(&pod.ty) && goal_ty_by_pos.get(&pos) == Some(&pod.ty)
+            });
+
+            if home {
+                pod.state = State::Done;
+            }
+        }
+    }
 }
 
-fn room_positions() -> impl Iterator<Item = Vec2> {
-    ROOM_X_POSITIONS
-        .iter()
-        .copied()
-        .flat_map(|x| (ROOM_Y..ROOM_Y + ROOM_SIZE).map(move |y| Vec2::new(x, y)))
+#[derive(Debug)]
+struct Input {
+    burrow: Burrow,
+    pods: Vec<Amphipod>,
 }
 
-fn spawn_amphipods(types: &[AmphipodType]) -> Vec<Amphipod> {
-    types
-        .iter()
-        .copied()
-        .zip(room_positions())
-        .map(|(ty, pos)| Amphipod {
-            pos,
-            ty,
-            state: State::Fresh,
-        })
-        .collect()
+/// Insert the part 2 rows after the first row of the rooms
+fn unfold(lines: &[String]) -> Result<Vec<String>> {
+    let first_room_line = (HALLWAY_Y + 1) as usize;
+
+    if lines.len() <= first_room_line {
+        bail!("Diagram has no rooms to unfold");
+    }
+
+    // The extra rows have to line up with the rooms they are inserted into
+    let room_xs = |line: &str| -> Vec<usize> {
+        line.char_indices()
+            .filter(|(_, ch)| !matches!(ch, '#' | ' '))
+            .map(|(x, _)| x)
+            .collect()
+    };
+
+    let diagram_room_xs = room_xs(&lines[first_room_line]);
+
+    if diagram_room_xs != room_xs(FOLDED_LINES[0]) {
+        bail!(
+            "Unfolded rows only fit the puzzle's 4 rooms, found {} rooms",
+            diagram_room_xs.len()
+        );
+    }
+
+    let mut unfolded = lines[..=first_room_line].to_vec();
+    unfolded.extend(FOLDED_LINES.iter().map(|line| line.to_string()));
+    unfolded.extend_from_slice(&lines[first_room_line + 1..]);
+
+    Ok(unfolded)
+}
+
+fn parse_lines(lines: &[String]) -> Result<Input> {
+    let width = lines.iter().map(|line| line.len()).max().unwrap_or(0) as i32;
+    let height = lines.len() as i32;
+
+    let extent = Extent2i::from_min_and_shape(Point2i::fill(0), PointN([width, height]));
+    let mut map: Map = Array2x1::fill(extent, WALL);
+
+    let mut hallway_xs = Vec::new();
+    let mut room_cells: Vec<Vec2> = Vec::new();
+    let mut pods = Vec::new();
+
+    for (y, line) in lines.iter().enumerate() {
+        let y = y as i32;
+
+        for (x, ch) in line.chars().enumerate() {
+            let pos = Vec2::new(x as i32, y);
+
+            let ty = match ch {
+                '#' | ' ' => continue,
+                '.' => None,
+                letter if letter.is_ascii_uppercase() => match AmphipodType::from_letter(letter) {
+                    Some(ty) => Some(ty),
+                    None => bail!(
+                        "Amphipod `{}` at {},{} is past {}",
+                        letter,
+                        x,
+                        y,
+                        AmphipodType::MAX_LETTER
+                    ),
+                },
+                other => bail!("Unexpected `{}` at {},{}", other, x, y),
+            };
+
+            if y == HALLWAY_Y {
+                *map.get_mut(PointN([pos.x, pos.y])) = HALLWAY;
+                hallway_xs.push(pos.x);
+            } else if y > HALLWAY_Y {
+                *map.get_mut(PointN([pos.x, pos.y])) = ROOM;
+                room_cells.push(pos);
+            } else {
+                bail!("Open space above the hallway at {},{}", x, y);
+            }
+
+            if let Some(ty) = ty {
+                let state = if y == HALLWAY_Y {
+                    State::WaitingInHall
+                } else {
+                    State::Fresh
+                };
+
+                pods.push(Amphipod { pos, ty, state });
+            }
+        }
+    }
+
+    let mut room_xs: Vec<i32> = room_cells.iter().map(|p| p.x).collect();
+    room_xs.sort_unstable();
+    room_xs.dedup();
+
+    let room_y = room_cells.iter().map(|p| p.y).min().unwrap_or(HALLWAY_Y + 1);
+    let room_size = room_cells.iter().map(|p| p.y).max().map(|y| y - room_y + 1).unwrap_or(0);
+
+    if room_xs.is_empty() {
+        bail!("Diagram has no rooms");
+    }
+
+    if room_y != HALLWAY_Y + 1 {
+        bail!("Rooms must open onto the hallway");
+    }
+
+    for room_x in room_xs.iter().copied() {
+        if !hallway_xs.contains(&room_x) {
+            bail!("Room at x={} does not open onto the hallway", room_x);
+        }
+
+        for room_y in room_y..room_y + room_size {
+            if !room_cells.contains(&Vec2::new(room_x, room_y)) {
+                bail!("Room at x={} is missing a space at y={}", room_x, room_y);
+            }
+        }
+    }
+
+    let burrow = Burrow {
+        map,
+        hallway_xs,
+        room_xs,
+        room_y,
+        room_size,
+    };
+
+    let mut types: Vec<AmphipodType> = pods.iter().map(|pod| pod.ty).collect();
+    types.sort();
+    types.dedup();
+
+    if types.len() != burrow.room_xs.len() {
+        bail!(
+            "{} amphipod types for {} rooms",
+            types.len(),
+            burrow.room_xs.len()
+        );
+    }
+
+    for ty in types {
+        let count = pods.iter().filter(|pod| pod.ty == ty).count() as i32;
+
+        if count != burrow.room_size {
+            bail!(
+                "{} {} amphipods but rooms hold {}",
+                count,
+                ty.as_letter(),
+                burrow.room_size
+            );
+        }
+    }
+
+    burrow.settle(&mut pods);
+
+    Ok(Input { burrow, pods })
+}
+
+/// Parse a burrow diagram, optionally unfolding the extra part 2 rows into it
+fn parse_input(reader: impl BufRead, unfold_rooms: bool) -> Result<Input> {
+    let lines = reader
+        .lines()
+        .collect::<Result<Vec<String>, _>>()?
+        .into_iter()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<String>>();
+
+    if unfold_rooms {
+        parse_lines(&unfold(&lines)?)
+    } else {
+        parse_lines(&lines)
+    }
 }
 
 struct DisplayMap<'a>(&'a Map, &'a [Amphipod]);
@@ -88,9 +299,8 @@ impl<'a> fmt::Display for DisplayMap<'a> {
                 let display_char = maybe_occupied_by
                     .map(|pod_ty| pod_ty.as_letter())
                     .unwrap_or_else(|| match self.0.get(PointN([x, y])) {
-                        0 => "#",
-                        1 => ".",
-                        other => "?",
+                        WALL => '#',
+                        _ => '.',
                     });
 
                 write!(f, "{}", display_char)?;
@@ -103,31 +313,26 @@ impl<'a> fmt::Display for DisplayMap<'a> {
     }
 }
 
+/// Amphipods are lettered from A, each letter costs ten times more to move than the last
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
-enum AmphipodType {
-    Amber,
-    Bronze,
-    Copper,
-    Desert,
-}
+struct AmphipodType(u8);
 
 impl AmphipodType {
-    fn as_letter(&self) -> &'static str {
-        match self {
-            AmphipodType::Amber => "A",
-            AmphipodType::Bronze => "B",
-            AmphipodType::Copper => "C",
-            AmphipodType::Desert => "D",
-        }
+    /// Letters past this cost too much per step to add up safely
+    const MAX_LETTER: char = 'J';
+
+    fn from_letter(letter: char) -> Option<Self> {
+        ('A'..=Self::MAX_LETTER)
+            .contains(&letter)
+            .then(|| AmphipodType(letter as u8 - b'A'))
     }
 
-    fn move_cost(&self) -> i32 {
-        match self {
-            AmphipodType::Amber => 1,
-            AmphipodType::Bronze => 10,
-            AmphipodType::Copper => 100,
-            AmphipodType::Desert => 1000,
-        }
+    fn as_letter(&self) -> char {
+        (b'A' + self.0) as char
+    }
+
+    fn move_cost(&self) -> Energy {
+        10i64.pow(self.0 as u32)
     }
 }
 
@@ -165,7 +370,7 @@ impl Amphipod {
             .filter(move |pos| is_walkable(map, *pos) && state != State::Done)
     }
 
-    fn move_cost(&self) -> i32 {
+    fn move_cost(&self) -> Energy {
         self.ty.move_cost()
     }
 
@@ -245,17 +450,18 @@ impl Amphipod {
 
     fn next_states(
         &self,
-        map: &Map,
+        burrow: &Burrow,
         goal_rooms: &HashMap<AmphipodType, HashSet<i32>>,
         availble_rooms: &Vec<bool>,
         occupied_locations: &HashSet<Vec2>
-    ) -> impl Iterator<Item = (i32, Amphipod)> {
+    ) -> impl Iterator<Item = (Energy, Amphipod)> {
         let mut next_states = Vec::new();
 
         match self.state {
             State::Fresh => {
-                for hallway_pos in hallway_positions() {
-                    if !ROOM_X_POSITIONS
+                for hallway_pos in burrow.hallway_positions() {
+                    if !burrow
+                        .room_xs
                         .iter()
                         .copied()
                         .all(|room_x| hallway_pos[0] != room_x)
@@ -273,7 +479,7 @@ impl Amphipod {
                     new_state.state = State::WaitingInHall;
 
                     let dist = (self.pos - hallway_pos).abs().sum();
-                    let cost = dist * self.move_cost();
+                    let cost = dist as Energy * self.move_cost();
                     next_states.push((cost, new_state));
                 }
             },
@@ -282,7 +488,8 @@ impl Amphipod {
 
         match self.state {
             State::Fresh | State::WaitingInHall => {
-                for (room_x, availible) in ROOM_X_POSITIONS
+                for (room_x, availible) in burrow
+                    .room_xs
                     .iter()
                     .copied()
                     .zip(availble_rooms.iter().copied())
@@ -300,7 +507,7 @@ impl Amphipod {
                         continue;
                     }
 
-                    for room_y in ROOM_Y..ROOM_Y + ROOM_SIZE {
+                    for room_y in burrow.room_ys() {
                         let room_pos = Vec2::new(room_x, room_y);
 
                         if self.pos != room_pos && occupied_locations.contains(&room_pos) {
@@ -316,7 +523,7 @@ impl Amphipod {
                         new_state.state = State::Done;
 
                         let d = dist(self.pos, room_pos);
-                        let cost = d * self.move_cost();
+                        let cost = d as Energy * self.move_cost();
                         next_states.push((cost, new_state));
                     }
                 }
@@ -351,17 +558,18 @@ fn check_goal(goal: &[Amphipod], state: &[Amphipod]) -> bool {
     state.iter().all(|pod| pod.state == State::Done)
 }
 
-fn determine_available_rooms(goal: &Vec<Amphipod>, pods: &Vec<Amphipod>) -> Vec<bool> {
+fn determine_available_rooms(burrow: &Burrow, goal: &Vec<Amphipod>, pods: &Vec<Amphipod>) -> Vec<bool> {
     let pod_ty_by_pos: HashMap<Vec2, AmphipodType> =
         pods.iter().map(|pod| (pod.pos, pod.ty)).collect();
     let goal_ty_by_pos: HashMap<Vec2, AmphipodType> =
         goal.iter().map(|pod| (pod.pos, pod.ty)).collect();
 
-    ROOM_X_POSITIONS
+    burrow
+        .room_xs
         .iter()
         .copied()
         .map(|room_x| {
-            (ROOM_Y..ROOM_Y + ROOM_SIZE).all(|room_y| {
+            burrow.room_ys().all(|room_y| {
                 let pos = Vec2::new(room_x, room_y);
 
                 if let Some(occupied_type) = pod_ty_by_pos.get(&pos) {
@@ -377,7 +585,7 @@ fn determine_available_rooms(goal: &Vec<Amphipod>, pods: &Vec<Amphipod>) -> Vec<
 
 #[derive(Debug, Eq)]
 struct VisitItem {
-    estimated_cost: i32,
+    estimated_cost: Energy,
     state: Vec<Amphipod>,
 }
 
@@ -399,9 +607,9 @@ impl Ord for VisitItem {
     }
 }
 
-fn heuristic(goal_rooms: &HashMap<AmphipodType, HashSet<i32>>, pods: &Vec<Amphipod>) -> i32 {
+fn heuristic(goal_rooms: &HashMap<AmphipodType, HashSet<i32>>, pods: &Vec<Amphipod>) -> Energy {
     pods.iter().map(|pod| {
-        let hallway_cost = (i32::abs(pod.pos[1] - 1) + 1) * pod.move_cost();
+        let hallway_cost = (i32::abs(pod.pos[1] - 1) + 1) as Energy * pod.move_cost();
         match pod.state {
             State::Fresh => {
                 let in_goal_already = goal_rooms.get(&pod.ty).map(|rooms| rooms.contains(&pod.pos[0])).unwrap_or(false);
@@ -413,11 +621,165 @@ fn heuristic(goal_rooms: &HashMap<AmphipodType, HashSet<i32>>, pods: &Vec<Amphip
     }).sum()
 }
 
+/// A single amphipod moving from one space to another, pod is its index in the
+/// starting list of amphipods
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Move {
+    pod: usize,
+    ty: AmphipodType,
+    from: Vec2,
+    to: Vec2,
+    cost: Energy,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} #{}: {},{} -> {},{} ({})",
+            self.ty.as_letter(),
+            self.pod,
+            self.from.x,
+            self.from.y,
+            self.to.x,
+            self.to.y,
+            self.cost
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Plan {
+    cost: Energy,
+    moves: Vec<Move>,
+}
+
+/// Every move and the burrow after it, starting from pods
+struct Replay<'a>(&'a Burrow, &'a [Amphipod], &'a [Move]);
+
+impl<'a> fmt::Display for Replay<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Replay(burrow, pods, moves) = self;
+        let mut pods = pods.to_vec();
+        let mut total = 0;
+
+        writeln!(f, "Start")?;
+        writeln!(f, "{}", DisplayMap(&burrow.map, &pods))?;
+
+        for (turn, mv) in moves.iter().enumerate() {
+            pods[mv.pod].pos = mv.to;
+            total += mv.cost;
+
+            writeln!(f, "Move {}: {} total: {}", turn + 1, mv, total)?;
+            writeln!(f, "{}", DisplayMap(&burrow.map, &pods))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Check every move follows the rules starting from pods and return the total energy
+fn validate_moves(burrow: &Burrow, pods: &[Amphipod], moves: &[Move]) -> Result<Energy> {
+    let goal_ty_by_x: HashMap<i32, AmphipodType> = burrow
+        .goal(pods)
+        .iter()
+        .map(|pod| (pod.pos.x, pod.ty))
+        .collect();
+
+    let mut pods = pods.to_vec();
+    let mut total = 0;
+
+    for (turn, mv) in moves.iter().enumerate() {
+        let turn = turn + 1;
+
+        let pod = match pods.get(mv.pod) {
+            Some(pod) => pod.clone(),
+            None => bail!("Move {}: no amphipod #{}", turn, mv.pod),
+        };
+
+        if pod.pos != mv.from || pod.ty != mv.ty {
+            bail!("Move {}: amphipod #{} is not a {} at {},{}", turn, mv.pod, mv.ty.as_letter(), mv.from.x, mv.from.y);
+        }
+
+        let from_hallway = mv.from.y == HALLWAY_Y;
+        let to_hallway = mv.to.y == HALLWAY_Y && burrow.hallway_xs.contains(&mv.to.x);
+        let to_room = burrow.room_positions().any(|pos| pos == mv.to);
+
+        if !to_hallway && !to_room {
+            bail!("Move {}: {},{} is not open space", turn, mv.to.x, mv.to.y);
+        }
+
+        if to_hallway {
+            if burrow.room_xs.contains(&mv.to.x) {
+                bail!("Move {}: stops outside a room", turn);
+            }
+
+            if from_hallway {
+                bail!("Move {}: moves along the hallway after stopping there", turn);
+            }
+        }
+
+        if to_room {
+            if !from_hallway && mv.from.x == mv.to.x {
+                bail!("Move {}: moves within a room", turn);
+            }
+
+            if goal_ty_by_x.get(&mv.to.x) != Some(&pod.ty) {
+                bail!("Move {}: {} enters a room that is not its own", turn, pod.ty.as_letter());
+            }
+
+            if pods.iter().any(|other| other.pos.x == mv.to.x && other.pos.y > HALLWAY_Y && other.ty != pod.ty) {
+                bail!("Move {}: room still holds other amphipods", turn);
+            }
+        }
+
+        let occupied_locations: HashSet<Vec2> = pods
+            .iter()
+            .enumerate()
+            .filter(|(id, _)| *id != mv.pod)
+            .map(|(_, pod)| pod.pos)
+            .collect();
+
+        if occupied_locations.contains(&mv.to) || !pod.check_path(&occupied_locations, mv.to) {
+            bail!("Move {}: path is blocked", turn);
+        }
+
+        let cost = dist(mv.from, mv.to) as Energy * pod.move_cost();
+
+        if cost != mv.cost {
+            bail!("Move {}: costs {} not {}", turn, cost, mv.cost);
+        }
+
+        pods[mv.pod].pos = mv.to;
+        total += cost;
+    }
+
+    Ok(total)
+}
+
+/// The move that turns state before into after
+fn move_between(before: &[Amphipod], after: &[Amphipod], cost: Energy) -> Move {
+    let (pod, (from, to)) = before
+        .iter()
+        .zip(after.iter())
+        .enumerate()
+        .find(|(_, (from, to))| from.pos != to.pos)
+        .expect("States differ by one move");
+
+    Move {
+        pod,
+        ty: from.ty,
+        from: from.pos,
+        to: to.pos,
+        cost,
+    }
+}
+
 fn find_lowest_energy_plan(
-    map: &Map,
+    burrow: &Burrow,
     goal: &Vec<Amphipod>,
     pods: Vec<Amphipod>,
-) -> (i32, Vec<Amphipod>) {
+) -> Plan {
     let goal_rooms_iter = goal.iter().map(|pod| (pod.ty, pod.pos[0]));
 
     let mut goal_rooms = HashMap::new();
@@ -433,7 +795,7 @@ fn find_lowest_energy_plan(
 
     let mut goal_configs = Vec::new();
 
-    let mut costs: HashMap<Vec<Amphipod>, i32> = HashMap::new();
+    let mut costs: HashMap<Vec<Amphipod>, Energy> = HashMap::new();
 
     let mut previous_links: HashMap<Vec<Amphipod>, Vec<Amphipod>> = HashMap::default();
 
@@ -442,7 +804,7 @@ fn find_lowest_energy_plan(
     //open.push(pods);
     visit_queue.push(VisitItem { estimated_cost: 0, state: pods });
 
-    let mut cheapest_solution = i32::MAX;
+    let mut cheapest_solution = Energy::MAX;
 
     //visited.insert(start_cost_and_state);
 
@@ -457,7 +819,7 @@ fn find_lowest_energy_plan(
                 visit_queue.len()
             );
 
-            //println!("{}", DisplayMap(map, &cur_state));
+            //println!("{}", DisplayMap(&burrow.map, &cur_state));
         }
 
         let cur_cost = costs[&cur_state];
@@ -466,7 +828,7 @@ fn find_lowest_energy_plan(
 
         if check_goal(goal, &cur_state) {
             goal_configs.push((cur_cost, cur_state));
-            cheapest_solution = i32::min(cheapest_solution, cur_cost);
+            cheapest_solution = Energy::min(cheapest_solution, cur_cost);
             continue;
         }
 
@@ -474,19 +836,19 @@ fn find_lowest_energy_plan(
             continue;
         }
 
-        let available_rooms = determine_available_rooms(goal, &cur_state);
+        let available_rooms = determine_available_rooms(burrow, goal, &cur_state);
 
         let occupied_locations: HashSet<Vec2> = cur_state.iter().map(|pod| pod.pos).collect();
 
         for (cur_pod_id, pod) in cur_state.iter().enumerate() {
-            for (action_cost, next_action) in pod.next_states(map, &goal_rooms, &available_rooms, &occupied_locations) {
+            for (action_cost, next_action) in pod.next_states(burrow, &goal_rooms, &available_rooms, &occupied_locations) {
                 let next_cost = action_cost + cur_cost;
                 let mut next_state = cur_state.clone();
                 next_state[cur_pod_id] = next_action;
 
 
 
-                if next_cost < costs.get(&next_state).copied().unwrap_or(i32::MAX) {
+                if next_cost < costs.get(&next_state).copied().unwrap_or(Energy::MAX) {
                     //open.push(next_state.clone());
                     previous_links.insert(next_state.clone(), cur_state.clone());
                     let estimated_cost = next_cost + heuristic(&goal_rooms, &next_state);
@@ -498,123 +860,204 @@ fn find_lowest_energy_plan(
         }
     }
 
-    let (min_cost, min_state) = goal_configs.into_iter().min_by_key(|(cost, state)| *cost).unwrap();
-
+    let (min_cost, min_state) = goal_configs.into_iter().min_by_key(|(cost, _)| *cost).unwrap();
 
-    println!("***** replay start *****");
+    let mut states = vec![min_state];
 
-    let mut states = Vec::new();
-    let mut maybe_cur_state = Some(min_state.clone());
-    states.push(min_state.clone());
+    while let Some(prev_state) = previous_links.get(states.last().unwrap()) {
+        states.push(prev_state.clone());
+    }
 
-    while let Some(cur_state) = maybe_cur_state {
-        let next_state = previous_links.get(&cur_state).cloned();
+    states.reverse();
 
-        if let Some(next_state) = next_state.clone() {
-            states.push(next_state);
-        }
+    let moves = states
+        .windows(2)
+        .map(|pair| move_between(&pair[0], &pair[1], costs[&pair[1]] - costs[&pair[0]]))
+        .collect();
 
-        maybe_cur_state = next_state;
+    Plan {
+        cost: min_cost,
+        moves,
     }
+}
 
-    states.reverse();
+fn solve(input: &Input) -> Plan {
+    let Input { burrow, pods } = input;
 
-    for (turn, state) in states.iter().enumerate() {
-        println!("Turn: {} cost: {}", turn, costs[state]);
-        println!("{}", DisplayMap(map, &state));
-    }
+    let goal = burrow.goal(pods);
+
+    find_lowest_energy_plan(burrow, &goal, pods.clone())
+}
 
-    println!("***** replay end *****");
+fn main() -> Result<()> {
+    let lines: Vec<String> = {
+        let stdin = io::stdin();
+        let stdin_lock = stdin.lock();
+        stdin_lock.lines().collect::<Result<_, _>>()?
+    };
 
-    (min_cost, min_state)
+    let input = parse_input(lines.join("\n").as_bytes(), false)?;
+    let part_1 = solve(&input);
+    println!("{}", Replay(&input.burrow, &input.pods, &part_1.moves));
+    validate_moves(&input.burrow, &input.pods, &part_1.moves)?;
+
+    let unfolded = parse_input(lines.join("\n").as_bytes(), true)?;
+    let part_2 = solve(&unfolded);
+    println!("{}", Replay(&unfolded.burrow, &unfolded.pods, &part_2.moves));
+    validate_moves(&unfolded.burrow, &unfolded.pods, &part_2.moves)?;
+
+    println!("cost: {}", part_1.cost);
+    println!("cost (unfolded): {}", part_2.cost);
+
+    Ok(())
 }
 
-fn main() {
-    let map = load_map();
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use crate::{
+        find_lowest_energy_plan, parse_input, validate_moves, AmphipodType, Energy, Input, Move, Replay,
+        State, Vec2,
+    };
 
-    // let pods = spawn_amphipods(&[
-    //     AmphipodType::Bronze,
-    //     AmphipodType::Amber,
-    //     AmphipodType::Copper,
-    //     AmphipodType::Desert,
-    //     AmphipodType::Bronze,
-    //     AmphipodType::Copper,
-    //     AmphipodType::Desert,
-    //     AmphipodType::Amber,
-    // ]);
+    fn get_test_input(unfold_rooms: bool) -> Input {
+        let test_data_str = include_str!("../test_input.txt");
 
-    // #############
-    // #...........#
-    // ###D#A#C#C###
-    //   #D#A#B#B#
-    //   #########
+        let test_data_reader = Cursor::new(test_data_str.to_owned());
 
+        parse_input(test_data_reader, unfold_rooms).unwrap()
+    }
 
-    // let pods = spawn_amphipods(&[
-    //     AmphipodType::Desert,
-    //     AmphipodType::Desert,
-    //     AmphipodType::Amber,
-    //     AmphipodType::Amber,
-    //     AmphipodType::Copper,
-    //     AmphipodType::Bronze,
-    //     AmphipodType::Copper,
-    //     AmphipodType::Bronze,
-    // ]);
+    fn parse_str(s: &str) -> anyhow::Result<Input> {
+        parse_input(Cursor::new(s.to_owned()), false)
+    }
 
-    // #############
-    // #...........#
-    // ###D#A#C#C###
-    //   #D#C#B#A#
-    //   #D#B#A#C#
-    //   #D#A#B#B#
-    //   #########
+    #[test]
+    fn test_parse() {
+        let Input { burrow, pods } = get_test_input(false);
+
+        assert_eq!(burrow.hallway_xs, (1..=11).collect::<Vec<i32>>());
+        assert_eq!(burrow.room_xs, vec![3, 5, 7, 9]);
+        assert_eq!(burrow.room_y, 2);
+        assert_eq!(burrow.room_size, 2);
+        assert_eq!(pods.len(), 8);
+        assert_eq!(pods[0].pos, Vec2::new(3, 2));
+        assert_eq!(pods[0].ty, AmphipodType::from_letter('B').unwrap());
+    }
 
+    #[test]
+    fn test_already_home_pods_are_done() {
+        let Input { pods, .. } = get_test_input(false);
 
-    let pods = spawn_amphipods(&[
-        AmphipodType::Desert,
-        AmphipodType::Desert,
-        AmphipodType::Desert,
-        AmphipodType::Desert,
+        let done: Vec<Vec2> = pods
+            .iter()
+            .filter(|pod| pod.state == State::Done)
+            .map(|pod| pod.pos)
+            .collect();
 
-        AmphipodType::Amber,
-        AmphipodType::Copper,
-        AmphipodType::Bronze,
-        AmphipodType::Amber,
+        assert_eq!(done, vec![Vec2::new(3, 3), Vec2::new(7, 3)]);
+    }
 
-        AmphipodType::Copper,
-        AmphipodType::Bronze,
-        AmphipodType::Amber,
-        AmphipodType::Bronze,
+    #[test]
+    fn test_parse_unfolded() {
+        let Input { burrow, pods } = get_test_input(true);
 
-        AmphipodType::Copper,
-        AmphipodType::Amber,
-        AmphipodType::Copper,
-        AmphipodType::Bronze,
-    ]);
+        assert_eq!(burrow.room_size, 4);
+        assert_eq!(pods.len(), 16);
+    }
 
+    #[test]
+    fn test_letter_costs() {
+        assert_eq!(AmphipodType::from_letter('A').unwrap().move_cost(), 1);
+        assert_eq!(AmphipodType::from_letter('D').unwrap().move_cost(), 1000);
+        assert_eq!(AmphipodType::from_letter('E').unwrap().move_cost(), 10000);
+        assert_eq!(AmphipodType::from_letter('J').unwrap().move_cost(), 1_000_000_000);
+        assert_eq!(AmphipodType::from_letter('K'), None);
+        assert_eq!(AmphipodType::from_letter('@'), None);
+    }
 
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_str("#####\n#...#\n###A#\n  ###\n").is_ok());
+        // Two types for a single room
+        assert!(parse_str("#####\n#...#\n###A#\n  #B#\n  ###\n").is_err());
+        // Room only partially open
+        assert!(parse_str("#######\n#.....#\n###A#B#\n  #A###\n  ###\n").is_err());
+        // Letters past J
+        assert!(parse_str("#####\n#...#\n###K#\n  ###\n").is_err());
+    }
 
-    println!("{}", DisplayMap(&map, &pods));
+    #[test]
+    fn test_unfold_needs_four_rooms() {
+        let two_rooms = "#######\n#.....#\n###B#A#\n  #####\n";
 
-    let mut goal_ty: Vec<AmphipodType> = pods.iter().map(|p| p.ty).collect();
-    goal_ty.sort();
-    let mut goal = spawn_amphipods(&goal_ty);
+        assert!(parse_str(two_rooms).is_ok());
+        assert!(parse_input(Cursor::new(two_rooms.to_owned()), true).is_err());
+    }
 
-    for pod in goal.iter_mut() {
-        pod.state = State::Done;
+    #[test]
+    fn test_small_burrow() {
+        // Two rooms of depth one just need to swap
+        let Input { burrow, pods } = parse_str("#######\n#.....#\n###B#A#\n  #####\n").unwrap();
+        let goal = burrow.goal(&pods);
+
+        let plan = find_lowest_energy_plan(&burrow, &goal, pods);
+
+        // A steps out into the hallway left of room one (4 * 1), B moves into
+        // room two (4 * 10) then A moves into room one (2 * 1)
+        assert_eq!(plan.cost, 46);
     }
 
-    println!("{}", DisplayMap(&map, &goal));
+    #[test]
+    fn test_example() {
+        let Input { burrow, pods } = get_test_input(false);
+        let goal = burrow.goal(&pods);
 
-    let maybe_best_plan = find_lowest_energy_plan(&map, &goal, pods);
+        let plan = find_lowest_energy_plan(&burrow, &goal, pods.clone());
 
-    let (best_plan_cost, best_plan) = maybe_best_plan;
+        assert_eq!(plan.cost, 12521);
+        assert_eq!(plan.moves.iter().map(|mv| mv.cost).sum::<Energy>(), 12521);
+        assert_eq!(validate_moves(&burrow, &pods, &plan.moves).unwrap(), 12521);
 
-    //println!("{}", DisplayMap(&map, &best_plan));
+        let replay = Replay(&burrow, &pods, &plan.moves).to_string();
+        assert!(replay.ends_with("#############\n#...........#\n###A#B#C#D###\n###A#B#C#D###\n#############\n\n"));
+    }
 
-    println!("cost: {}", best_plan_cost);
-}
+    fn small_move(pod: usize, letter: char, from: (i32, i32), to: (i32, i32), cost: Energy) -> Move {
+        Move {
+            pod,
+            ty: AmphipodType::from_letter(letter).unwrap(),
+            from: Vec2::new(from.0, from.1),
+            to: Vec2::new(to.0, to.1),
+            cost,
+        }
+    }
 
-#[cfg(test)]
-mod test {
+    #[test]
+    fn test_validate_moves() {
+        let Input { burrow, pods } = parse_str("#######\n#.....#\n###B#A#\n  #####\n").unwrap();
+
+        let out = small_move(1, 'A', (5, 2), (2, 1), 4);
+        let b_home = small_move(0, 'B', (3, 2), (5, 2), 40);
+        let a_home = small_move(1, 'A', (2, 1), (3, 2), 2);
+
+        assert_eq!(
+            validate_moves(&burrow, &pods, &[out.clone(), b_home.clone(), a_home]).unwrap(),
+            46
+        );
+
+        // Wrong cost
+        assert!(validate_moves(&burrow, &pods, &[small_move(1, 'A', (5, 2), (2, 1), 3)]).is_err());
+        // Stopping outside a room
+        assert!(validate_moves(&burrow, &pods, &[small_move(1, 'A', (5, 2), (3, 1), 3)]).is_err());
+        // B cannot go home while A is still in its room
+        assert!(validate_moves(&burrow, &pods, &[b_home]).is_err());
+        // A cannot enter the room for B
+        assert!(validate_moves(&burrow, &pods, &[out.clone(), small_move(1, 'A', (2, 1), (5, 2), 4)]).is_err());
+        // Moving along the hallway after stopping
+        assert!(validate_moves(&burrow, &pods, &[out.clone(), small_move(1, 'A', (2, 1), (1, 1), 1)]).is_err());
+        // Wrong amphipod
+        assert!(validate_moves(&burrow, &pods, &[small_move(0, 'A', (5, 2), (2, 1), 4)]).is_err());
+    }
 }
@@ -1,87 +1,126 @@
-use std::{
-    io::{self, BufRead},
-    iter::repeat,
-};
-
-const BIT_SIZE: usize = 12;
-
-fn most_common_bit(count: i32) -> Option<u32> {
-    if count == 0 {
-        None
-    } else if count > 0 {
-        Some(1)
-    } else {
-        Some(0)
+mod reading;
+
+use std::io::{self, BufRead};
+
+use anyhow::{bail, Result};
+use reading::{column_counts, Reading};
+
+/// Which bit to keep when a column has as many ones as zeros
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tie {
+    Ones,
+    Zeros,
+}
+
+impl Tie {
+    fn wanted_bit(&self) -> bool {
+        *self == Tie::Ones
     }
 }
 
-fn gamma(counts: &[i32]) -> u32 {
-    counts
-        .into_iter()
-        .enumerate()
-        .map(|(n, count)| most_common_bit(*count).unwrap() << n)
-        .reduce(|a, b| a | b)
-        .unwrap()
+/// Picks the bit a row needs in a column to stay in the running, None keeps every row
+trait Criterion {
+    fn wanted_bit(&self, ones: usize, zeros: usize) -> Option<bool>;
 }
 
-fn get_counts(bit_size: usize, readings: &[u32]) -> Vec<i32> {
-    let counts_init: Vec<i32> = repeat(0).take(bit_size).collect();
+impl<F: Fn(usize, usize) -> Option<bool>> Criterion for F {
+    fn wanted_bit(&self, ones: usize, zeros: usize) -> Option<bool> {
+        self(ones, zeros)
+    }
+}
 
-    let counts = readings.iter().fold(counts_init, |mut counts, reading| {
-        for bit_index in 0..bit_size {
-            let inc = if (reading & (1 << bit_index)) != 0 {
-                1
-            } else {
-                -1
-            };
+struct MostCommon(Tie);
 
-            counts[bit_index] += inc;
+impl Criterion for MostCommon {
+    fn wanted_bit(&self, ones: usize, zeros: usize) -> Option<bool> {
+        if ones == zeros {
+            Some(self.0.wanted_bit())
+        } else {
+            Some(ones > zeros)
         }
+    }
+}
+
+struct LeastCommon(Tie);
 
+impl Criterion for LeastCommon {
+    fn wanted_bit(&self, ones: usize, zeros: usize) -> Option<bool> {
+        if ones == zeros {
+            Some(self.0.wanted_bit())
+        } else {
+            Some(ones < zeros)
+        }
+    }
+}
+
+fn gamma(counts: &[usize], row_count: usize) -> Reading {
+    let criterion = MostCommon(Tie::Ones);
+
+    Reading::from_bits(
         counts
-    });
+            .iter()
+            .map(|ones| criterion.wanted_bit(*ones, row_count - ones).unwrap()),
+    )
+}
 
-    counts
+/// Rows dropped while looking at a single column
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Elimination {
+    column: usize,
+    wanted_bit: Option<bool>,
+    /// Indices into the original readings
+    rows: Vec<usize>,
 }
 
-fn get_system_reading<F>(readings: Vec<u32>, criteria: F) -> u32
-where
-    F: Fn(i32) -> u32,
-{
-    let mut candidate_readings = readings;
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SystemReading {
+    reading: Reading,
+    row: usize,
+    eliminations: Vec<Elimination>,
+}
+
+/// Filter the readings one column at a time from the left until only one is left
+fn get_system_reading(readings: &[Reading], criterion: &impl Criterion) -> Result<SystemReading> {
+    let width = readings.first().map(Reading::width).unwrap_or(0);
+
+    let mut candidates: Vec<usize> = (0..readings.len()).collect();
+    let mut eliminations = Vec::new();
 
-    for bit_index_n in 0..BIT_SIZE {
-        if candidate_readings.len() == 1 {
+    for column in 0..width {
+        if candidates.len() <= 1 {
             break;
         }
 
-        let bit_index = BIT_SIZE - bit_index_n - 1;
-        let counts = get_counts(BIT_SIZE, &candidate_readings);
-        let needed_bit = criteria(counts[bit_index]);
+        let ones = candidates.iter().filter(|row| readings[**row].get(column)).count();
+        let wanted_bit = criterion.wanted_bit(ones, candidates.len() - ones);
 
-        let new_candidate_readings: Vec<u32> = candidate_readings
-            .iter()
-            .copied()
-            .filter(|reading| {
-                let reading_bit = (reading >> bit_index) & 1;
-                reading_bit == needed_bit
-            })
-            .collect();
+        let (kept, eliminated): (Vec<usize>, Vec<usize>) = candidates
+            .into_iter()
+            .partition(|row| wanted_bit.is_none_or(|bit| readings[*row].get(column) == bit));
+
+        eliminations.push(Elimination {
+            column,
+            wanted_bit,
+            rows: eliminated,
+        });
 
-        candidate_readings = new_candidate_readings;
+        candidates = kept;
     }
 
-    assert_eq!(candidate_readings.len(), 1);
-    candidate_readings
-        .into_iter()
-        .next()
-        .expect("System should have at least one remaining valid reading")
+    match candidates[..] {
+        [row] => Ok(SystemReading {
+            reading: readings[row].clone(),
+            row,
+            eliminations,
+        }),
+        _ => bail!("{} readings left after every column", candidates.len()),
+    }
 }
 
-fn parse_readings(lines: Vec<String>) -> Vec<u32> {
+fn parse_readings(lines: Vec<String>) -> Vec<Reading> {
     lines
-        .into_iter()
-        .map(|s| u32::from_str_radix(s.trim(), 2))
+        .iter()
+        .map(|s| Reading::parse(s))
         .collect::<Result<Vec<_>, _>>()
         .unwrap()
 }
@@ -94,35 +133,44 @@ fn main() {
     };
 
     let reading = parse_readings(lines);
+    let width = reading[0].width();
 
-    let counts = get_counts(BIT_SIZE, &reading);
-    let gamma: u32 = gamma(&counts);
+    let counts = column_counts(&reading, width);
+    let gamma = gamma(&counts, reading.len());
+    let epsilon = gamma.invert();
 
-    let epsilon = (!gamma) & ((1 << (BIT_SIZE)) - 1);
+    println!("gamma: {}", gamma);
+    println!("epsilon: {}", epsilon);
 
-    dbg!(gamma);
-    dbg!(epsilon);
+    if let (Some(gamma), Some(epsilon)) = (gamma.to_u128(), epsilon.to_u128()) {
+        println!("{}", gamma * epsilon);
+    }
 
-    println!("{}", gamma * epsilon);
+    let oxygen_reading = get_system_reading(&reading, &MostCommon(Tie::Ones)).unwrap();
+    let co2_reading = get_system_reading(&reading, &LeastCommon(Tie::Zeros)).unwrap();
 
-    let oxygen_reading = get_system_reading(reading.clone(), |bit_count| {
-        most_common_bit(bit_count).unwrap_or(1)
-    });
-    println!("oxygen_reading: {}", oxygen_reading);
+    for (name, system_reading) in [("oxygen", &oxygen_reading), ("c02", &co2_reading)] {
+        for elimination in system_reading.eliminations.iter() {
+            println!(
+                "{} column {}: wanted {:?}, eliminated rows {:?}",
+                name, elimination.column, elimination.wanted_bit, elimination.rows
+            );
+        }
 
-    let co2_reading = get_system_reading(reading.clone(), |bit_count| {
-        !most_common_bit(bit_count).unwrap_or(1) & 1
-    });
-    println!("c02_reading: {}", co2_reading);
+        println!("{}_reading: {} (row {})", name, system_reading.reading, system_reading.row);
+    }
 
-    println!("rating: {}", oxygen_reading * co2_reading);
+    if let (Some(oxygen), Some(co2)) = (oxygen_reading.reading.to_u128(), co2_reading.reading.to_u128()) {
+        println!("rating: {}", oxygen * co2);
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::{gamma, get_counts, parse_readings};
+    use crate::reading::{column_counts, Reading};
+    use crate::{gamma, get_system_reading, parse_readings, LeastCommon, MostCommon, Tie};
 
-    fn get_test_readings() -> Vec<u32> {
+    fn get_test_readings() -> Vec<Reading> {
         let test_data_str = include_str!("../test_input.txt");
         let lines: Vec<String> = test_data_str.lines().map(str::to_string).collect();
         parse_readings(lines)
@@ -130,7 +178,10 @@ mod test {
 
     #[test]
     fn test_parse() {
-        let actual = get_test_readings();
+        let actual: Vec<u128> = get_test_readings()
+            .iter()
+            .map(|reading| reading.to_u128().unwrap())
+            .collect();
 
         assert_eq!(
             actual,
@@ -144,9 +195,47 @@ mod test {
     #[test]
     fn test_gamma() {
         let readings = get_test_readings();
-        let counts = get_counts(5, &readings);
-        let gamma = gamma(&counts);
+        let counts = column_counts(&readings, 5);
+        let gamma = gamma(&counts, readings.len());
 
-        assert_eq!(gamma, 22);
+        assert_eq!(gamma.to_u128(), Some(22));
+        assert_eq!(gamma.invert().to_u128(), Some(9));
+    }
+
+    #[test]
+    fn test_system_readings() {
+        let readings = get_test_readings();
+
+        let oxygen = get_system_reading(&readings, &MostCommon(Tie::Ones)).unwrap();
+        assert_eq!(oxygen.reading.to_u128(), Some(23));
+        assert_eq!(oxygen.row, 3);
+
+        // The first column keeps the 7 readings starting with 1
+        assert_eq!(oxygen.eliminations[0].wanted_bit, Some(true));
+        assert_eq!(oxygen.eliminations[0].rows, vec![0, 5, 6, 10, 11]);
+        assert_eq!(oxygen.eliminations.len(), 5);
+
+        let co2 = get_system_reading(&readings, &LeastCommon(Tie::Zeros)).unwrap();
+        assert_eq!(co2.reading.to_u128(), Some(10));
+        assert_eq!(co2.eliminations.len(), 3);
+    }
+
+    #[test]
+    fn test_custom_criteria() {
+        let keep_ties = |ones: usize, zeros: usize| {
+            if ones == zeros { None } else { Some(ones < zeros) }
+        };
+
+        let readings = parse_readings(vec!["01".to_string(), "10".to_string(), "11".to_string()]);
+        let least_common = get_system_reading(&readings, &keep_ties).unwrap();
+        assert_eq!(least_common.row, 0);
+
+        let always_ones = get_system_reading(&readings, &|_, _| Some(true)).unwrap();
+        assert_eq!(always_ones.row, 2);
+
+        // Every column ties so nothing is ever eliminated
+        let tied = parse_readings(vec!["01".to_string(), "10".to_string()]);
+        assert!(get_system_reading(&tied, &keep_ties).is_err());
+        assert_eq!(get_system_reading(&tied, &LeastCommon(Tie::Zeros)).unwrap().row, 0);
     }
 }
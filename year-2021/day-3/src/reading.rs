@@ -0,0 +1,162 @@
+use std::fmt;
+
+use anyhow::{bail, Result};
+
+/// A row of diagnostic bits of any width, column 0 is the leftmost bit
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Reading {
+    /// Column n is bit n % 64 of word n / 64
+    words: Vec<u64>,
+    width: usize,
+}
+
+impl Reading {
+    pub fn parse(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let mut words = vec![0u64; s.len().div_ceil(64)];
+
+        for (column, ch) in s.chars().enumerate() {
+            match ch {
+                '0' => {}
+                '1' => words[column / 64] |= 1 << (column % 64),
+                _ => bail!("Invalid bit `{}` in reading {}", ch, s),
+            }
+        }
+
+        Ok(Reading {
+            words,
+            width: s.len(),
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get(&self, column: usize) -> bool {
+        (self.words[column / 64] >> (column % 64)) & 1 == 1
+    }
+
+    pub fn from_bits(bits: impl IntoIterator<Item = bool>) -> Self {
+        let mut reading = Reading {
+            words: Vec::new(),
+            width: 0,
+        };
+
+        for bit in bits {
+            if reading.width.is_multiple_of(64) {
+                reading.words.push(0);
+            }
+
+            if bit {
+                reading.words[reading.width / 64] |= 1 << (reading.width % 64);
+            }
+
+            reading.width += 1;
+        }
+
+        reading
+    }
+
+    /// Flip every bit
+    pub fn invert(&self) -> Self {
+        Reading::from_bits((0..self.width).map(|column| !self.get(column)))
+    }
+
+    /// Value with the leftmost bit most significant, None if it doesn't fit
+    pub fn to_u128(&self) -> Option<u128> {
+        if self.width > 128 {
+            return None;
+        }
+
+        Some((0..self.width).fold(0, |value, column| value << 1 | self.get(column) as u128))
+    }
+}
+
+impl fmt::Display for Reading {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for column in 0..self.width {
+            write!(f, "{}", if self.get(column) { '1' } else { '0' })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Number of ones in each column. Counters are kept bit sliced so each word of
+/// a reading updates 64 columns at once with a ripple of ands and xors.
+pub fn column_counts<'a>(readings: impl IntoIterator<Item = &'a Reading>, width: usize) -> Vec<usize> {
+    let word_count = width.div_ceil(64);
+
+    // planes[w][k] holds bit k of the count for each of the 64 columns in word w
+    let mut planes: Vec<Vec<u64>> = vec![Vec::new(); word_count];
+
+    for reading in readings {
+        assert_eq!(reading.width, width, "Readings must all be the same width");
+
+        for (word_planes, word) in planes.iter_mut().zip(reading.words.iter().copied()) {
+            let mut carry = word;
+
+            for plane in word_planes.iter_mut() {
+                if carry == 0 {
+                    break;
+                }
+
+                let next_carry = *plane & carry;
+                *plane ^= carry;
+                carry = next_carry;
+            }
+
+            if carry != 0 {
+                word_planes.push(carry);
+            }
+        }
+    }
+
+    (0..width)
+        .map(|column| {
+            planes[column / 64]
+                .iter()
+                .enumerate()
+                .map(|(k, plane)| (((plane >> (column % 64)) & 1) as usize) << k)
+                .sum()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{column_counts, Reading};
+
+    #[test]
+    fn test_wide_reading() {
+        let s = "1".repeat(70) + &"01".repeat(40);
+        let reading = Reading::parse(&s).unwrap();
+
+        assert_eq!(reading.width(), 150);
+        assert!(reading.get(69));
+        assert!(!reading.get(70));
+        assert!(reading.get(149));
+        assert_eq!(reading.to_string(), s);
+        assert_eq!(reading.to_u128(), None);
+        assert_eq!(reading.invert().invert(), reading);
+
+        assert!(Reading::parse("0120").is_err());
+    }
+
+    #[test]
+    fn test_column_counts() {
+        // Column n is set in every reading whose index has bit n % 7 set
+        let width = 130;
+        let readings: Vec<Reading> = (0..100usize)
+            .map(|i| Reading::from_bits((0..width).map(|column| (i >> (column % 7)) & 1 == 1)))
+            .collect();
+
+        let counts = column_counts(&readings, width);
+
+        for (column, count) in counts.iter().enumerate() {
+            let expected = readings.iter().filter(|r| r.get(column)).count();
+            assert_eq!(*count, expected, "column {}", column);
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::{HashMap, VecDeque};
+
+use ndarray::Array2;
+
+use crate::Board;
+
+/// Where a number shows up, a board can have the same number more than once
+#[derive(Debug, Clone, Copy)]
+struct Cell {
+    board_id: usize,
+    row: usize,
+    col: usize,
+}
+
+/// Every board along with where each number can be found on them so marking
+/// a drawing only touches the cells holding it
+pub struct BingoEngine {
+    boards: Vec<Board>,
+    cells_by_number: HashMap<i32, Vec<Cell>>,
+    diagonals: bool,
+}
+
+impl BingoEngine {
+    /// Boards can be any size and don't all need to match
+    pub fn new(boards: Vec<Board>) -> Self {
+        let mut cells_by_number: HashMap<i32, Vec<Cell>> = HashMap::new();
+
+        for (board_id, board) in boards.iter().enumerate() {
+            for ((row, col), num) in board.indexed_iter() {
+                cells_by_number
+                    .entry(*num)
+                    .or_default()
+                    .push(Cell { board_id, row, col });
+            }
+        }
+
+        BingoEngine {
+            boards,
+            cells_by_number,
+            diagonals: false,
+        }
+    }
+
+    /// Also win by completing either diagonal, only square boards have them
+    pub fn with_diagonals(mut self, diagonals: bool) -> Self {
+        self.diagonals = diagonals;
+        self
+    }
+
+    /// Call out each number in turn
+    pub fn play<'a>(&'a self, drawings: &'a [i32]) -> Events<'a> {
+        Events {
+            engine: self,
+            drawings: drawings.iter().copied().enumerate(),
+            boards: self.boards.iter().map(BoardState::new).collect(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Keep track of the order boards won in from a game's events
+    pub fn results(&self, events: impl IntoIterator<Item = Event>) -> Results {
+        let wins = events
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Won(win) => Some(win),
+                Event::Drawn { .. } => None,
+            })
+            .collect();
+
+        Results {
+            wins,
+            board_count: self.boards.len(),
+        }
+    }
+}
+
+/// Marks on a single board and how close each line is to being complete
+struct BoardState {
+    marked: Array2<bool>,
+    row_marks: Vec<usize>,
+    col_marks: Vec<usize>,
+    /// Top left to bottom right then top right to bottom left
+    diagonal_marks: [usize; 2],
+    unmarked_sum: i32,
+    won: bool,
+}
+
+impl BoardState {
+    fn new(board: &Board) -> Self {
+        let (rows, cols) = board.dim();
+
+        BoardState {
+            marked: Array2::from_elem((rows, cols), false),
+            row_marks: vec![0; rows],
+            col_marks: vec![0; cols],
+            diagonal_marks: [0; 2],
+            unmarked_sum: board.sum(),
+            won: false,
+        }
+    }
+
+    /// Returns true if marking this cell completes a line
+    fn mark(&mut self, board: &Board, row: usize, col: usize, diagonals: bool) -> bool {
+        if self.marked[(row, col)] {
+            return false;
+        }
+
+        self.marked[(row, col)] = true;
+        self.unmarked_sum -= board[(row, col)];
+
+        let (rows, cols) = board.dim();
+
+        self.row_marks[row] += 1;
+        self.col_marks[col] += 1;
+
+        let mut bingo = self.row_marks[row] == cols || self.col_marks[col] == rows;
+
+        if diagonals && rows == cols {
+            if row == col {
+                self.diagonal_marks[0] += 1;
+                bingo |= self.diagonal_marks[0] == rows;
+            }
+
+            if row + col + 1 == cols {
+                self.diagonal_marks[1] += 1;
+                bingo |= self.diagonal_marks[1] == rows;
+            }
+        }
+
+        bingo
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Win {
+    pub board_id: usize,
+    /// Index into the drawings of the winning number
+    pub draw: usize,
+    pub number: i32,
+    /// Sum of the unmarked numbers multiplied by the winning number
+    pub score: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Drawn { draw: usize, number: i32 },
+    /// Follows the Drawn event that completed a line, boards that win on the
+    /// same number come out in board order
+    Won(Win),
+}
+
+pub struct Events<'a> {
+    engine: &'a BingoEngine,
+    drawings: std::iter::Enumerate<std::iter::Copied<std::slice::Iter<'a, i32>>>,
+    boards: Vec<BoardState>,
+    pending: VecDeque<Event>,
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        if let Some(event) = self.pending.pop_front() {
+            return Some(event);
+        }
+
+        let (draw, number) = self.drawings.next()?;
+
+        let mut winners = Vec::new();
+
+        for cell in self.engine.cells_by_number.get(&number).into_iter().flatten() {
+            let state = &mut self.boards[cell.board_id];
+            let board = &self.engine.boards[cell.board_id];
+
+            if state.mark(board, cell.row, cell.col, self.engine.diagonals) && !state.won {
+                state.won = true;
+                winners.push(cell.board_id);
+            }
+        }
+
+        winners.sort_unstable();
+
+        // Only score once every copy of the number is marked
+        for board_id in winners {
+            self.pending.push_back(Event::Won(Win {
+                board_id,
+                draw,
+                number,
+                score: self.boards[board_id].unmarked_sum * number,
+            }));
+        }
+
+        Some(Event::Drawn { draw, number })
+    }
+}
+
+/// Every win in the order they happened
+#[derive(Debug, Clone)]
+pub struct Results {
+    pub wins: Vec<Win>,
+    board_count: usize,
+}
+
+impl Results {
+    pub fn first_winner(&self) -> Option<&Win> {
+        self.wins.first()
+    }
+
+    /// None unless every board won
+    pub fn last_winner(&self) -> Option<&Win> {
+        if self.wins.len() < self.board_count {
+            return None;
+        }
+
+        self.wins.last()
+    }
+
+    /// Board ids from first to win to last, boards that never won are left out
+    pub fn rank_order(&self) -> Vec<usize> {
+        self.wins.iter().map(|win| win.board_id).collect()
+    }
+
+    /// 0 for the first board to win, boards that win on the same number
+    /// share a rank
+    pub fn rank(&self, board_id: usize) -> Option<usize> {
+        let win = self.wins.iter().find(|win| win.board_id == board_id)?;
+
+        Some(self.wins.iter().take_while(|other| other.draw < win.draw).count())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ndarray::array;
+
+    use super::{BingoEngine, Event};
+    use crate::test::get_test_input;
+
+    #[test]
+    fn test_example() {
+        let (drawings, boards) = get_test_input();
+        let engine = BingoEngine::new(boards);
+        let results = engine.results(engine.play(&drawings));
+
+        let first = results.first_winner().unwrap();
+        assert_eq!((first.board_id, first.number, first.score), (2, 24, 4512));
+
+        let last = results.last_winner().unwrap();
+        assert_eq!((last.board_id, last.number, last.score), (1, 13, 1924));
+
+        assert_eq!(results.rank_order(), vec![2, 0, 1]);
+        assert_eq!(results.rank(1), Some(2));
+    }
+
+    #[test]
+    fn test_events() {
+        let (drawings, boards) = get_test_input();
+        let engine = BingoEngine::new(boards);
+
+        let events: Vec<Event> = engine.play(&drawings[..12]).collect();
+
+        assert_eq!(events.len(), 13);
+        assert_eq!(events[0], Event::Drawn { draw: 0, number: 7 });
+        assert!(matches!(events[11], Event::Drawn { draw: 11, number: 24 }));
+        assert!(matches!(events[12], Event::Won(win) if win.board_id == 2 && win.draw == 11));
+    }
+
+    #[test]
+    fn test_diagonals_and_sizes() {
+        let boards = vec![
+            array![[1, 2, 3], [4, 5, 6], [7, 8, 9]],
+            array![[3, 10], [11, 5], [7, 12]],
+        ];
+
+        let drawings = [3, 5, 7, 1, 9];
+
+        // 3, 5, 7 is only a line on a diagonal
+        let engine = BingoEngine::new(boards.clone());
+        let plain = engine.results(engine.play(&drawings));
+        assert!(plain.wins.is_empty());
+        assert_eq!(plain.first_winner(), None);
+
+        let engine = BingoEngine::new(boards).with_diagonals(true);
+        let diagonal = engine.results(engine.play(&drawings));
+        assert_eq!(diagonal.rank_order(), vec![0]);
+        assert_eq!(diagonal.first_winner().unwrap().score, (1 + 2 + 4 + 6 + 8 + 9) * 7);
+
+        // Not square so the 2x3 board has no diagonals and never finishes
+        assert_eq!(diagonal.last_winner(), None);
+    }
+
+    #[test]
+    fn test_shared_rank() {
+        let boards = vec![array![[1, 2], [3, 4]], array![[5, 6], [1, 2]], array![[9, 8], [7, 1]]];
+
+        let engine = BingoEngine::new(boards);
+        let results = engine.results(engine.play(&[1, 2, 9, 7]));
+
+        assert_eq!(results.rank_order(), vec![0, 1, 2]);
+        assert_eq!(results.rank(0), Some(0));
+        assert_eq!(results.rank(1), Some(0));
+        assert_eq!(results.rank(2), Some(2));
+        assert_eq!(results.last_winner().unwrap().score, 8 * 7);
+    }
+}
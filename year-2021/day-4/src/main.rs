@@ -1,12 +1,14 @@
+mod bingo;
+
 use std::{
-    collections::HashSet,
+    env,
     io::{self, BufRead},
 };
 
+use bingo::{BingoEngine, Event};
 use ndarray::{Array1, Array2};
 
 type Board = Array2<i32>;
-type BoardMarkings = Array2<i32>;
 
 fn parse_boards(mut reader: impl BufRead) -> (Vec<i32>, Vec<Board>) {
     let mut drawings_line = String::new();
@@ -88,79 +90,33 @@ fn main() {
         parse_boards(stdin_lock)
     };
 
-    let mut board_markings: Vec<BoardMarkings> = boards
-        .iter()
-        .map(|board| {
-            let s = board.shape();
-            BoardMarkings::zeros((s[0], s[1]))
-        })
-        .collect();
-
-    dbg!(&board_markings);
-
-    let mut winning_board_infos = Vec::new();
-
-    let mut winners = HashSet::new();
-
-    'find_winning_board: for drawing in drawings.iter().copied() {
-        for (board_id, board) in boards.iter().enumerate() {
-            if winners.contains(&board_id) {
-                continue;
-            }
-
-            let mut markings = &mut board_markings[board_id];
-
-            let s = board.shape();
-            let row_count = s[0];
-            let col_count = s[1];
-
-            for row in 0..row_count {
-                for col in 0..col_count {
-                    if board[(row, col)] == drawing {
-                        markings[(row, col)] = 1;
-                    }
-                }
-            }
-
-            let row_matches = markings
-                .rows()
-                .into_iter()
-                .any(|row| row.iter().all(|cell| *cell > 0));
-            let col_matches = markings
-                .columns()
-                .into_iter()
-                .any(|row| row.iter().all(|cell| *cell > 0));
-
-            let bingo = row_matches || col_matches;
-
-            if bingo {
-                winning_board_infos.push((board_id, drawing));
-                winners.insert(board_id);
-                //break 'find_winning_board;
-            }
+    // `diagonals` lets either diagonal complete a board too
+    let diagonals = env::args().nth(1).as_deref() == Some("diagonals");
+
+    let engine = BingoEngine::new(boards).with_diagonals(diagonals);
+    let events: Vec<Event> = engine.play(&drawings).collect();
+    let results = engine.results(events.iter().copied());
+
+    for event in events {
+        if let Event::Won(win) = event {
+            println!(
+                "board {} wins on {} with score {} (rank {})",
+                win.board_id,
+                win.number,
+                win.score,
+                results.rank(win.board_id).unwrap()
+            );
         }
     }
 
-    //dbg!(winning_board_info);
-
-    if let Some((winning_board_id, final_call)) = winning_board_infos.last() {
-        let winning_board = &boards[*winning_board_id];
-        let winning_markings = &board_markings[*winning_board_id];
-
-        dbg!(&winning_markings);
+    println!("boards in winning order: {:?}", results.rank_order());
 
-        let marked_inv = winning_markings.mapv(|cell| if cell > 0 { 0 } else { 1 });
-
-        dbg!(&marked_inv);
-
-        let only_marked = winning_board * marked_inv;
-
-        dbg!(&only_marked);
-
-        let raw_score: i32 = only_marked.iter().sum();
-        let score = raw_score * final_call;
+    if let Some(first) = results.first_winner() {
+        println!("first winner score: {}", first.score);
+    }
 
-        dbg!(score);
+    if let Some(last) = results.last_winner() {
+        println!("last winner score: {}", last.score);
     }
 }
 
@@ -172,7 +128,7 @@ mod test {
 
     use crate::{parse_boards, Board};
 
-    fn get_test_input() -> (Vec<i32>, Vec<Board>) {
+    pub(crate) fn get_test_input() -> (Vec<i32>, Vec<Board>) {
         let test_data_str = include_str!("../test_input.txt");
 
         let test_data_reader = Cursor::new(test_data_str.to_owned());
@@ -1,16 +1,16 @@
+mod vents;
+
 use std::{
-    collections::HashSet,
     convert::Infallible,
     io::{self, BufRead},
     str::FromStr,
 };
 
 use nalgebra::DimMax;
-use ndarray::Array2;
+use vents::{count_overlaps_dense, count_overlaps_sparse, count_overlaps_sweep};
 
 type Point = nalgebra::Point2<i32>;
 type Vec2 = nalgebra::Vector2<i32>;
-type Map = Array2<i32>;
 
 fn parse_point(s: &str) -> Point {
     let (x, y) = s.split_once(",").unwrap();
@@ -43,6 +43,38 @@ impl Line {
 
         d[0] == d[1]
     }
+
+    /// Smallest step between lattice points on the line, zero for a single point
+    fn step(&self) -> Vec2 {
+        let d = self.get_diff();
+        let divisor = gcd(d[0].abs(), d[1].abs());
+
+        if divisor == 0 {
+            d
+        } else {
+            d / divisor
+        }
+    }
+
+    /// Every integer point on the line from start to end
+    fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        let step = self.step();
+        let count = if step == Vec2::zeros() {
+            1
+        } else {
+            self.get_diff()[0].checked_div(step[0]).unwrap_or_else(|| self.get_diff()[1] / step[1]) + 1
+        };
+
+        (0..count).map(move |n| self.0 + step * n)
+    }
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
 }
 
 impl FromStr for Line {
@@ -71,46 +103,17 @@ fn main() {
 
     //dbg!(&lines);
 
-    //let valid_line: Vec<_> = lines.iter().filter(|l| l.horiz_or_vert()).collect();
-    let valid_line: Vec<_> = lines
+    let straight: Vec<&Line> = lines.iter().filter(|l| l.horiz_or_vert()).collect();
+    let with_diagonals: Vec<&Line> = lines
         .iter()
         .filter(|l| l.horiz_or_vert() || l.diagional())
         .collect();
 
-    dbg!(&valid_line);
-
-    let mut map = Map::zeros((1000, 1000));
-
-    let mut multiple_overlaps = HashSet::new();
-
-    for line in valid_line.iter() {
-        let mut p = line.0;
-        let diff = line.get_diff();
-
-        let d = diff.map(|e| i32::clamp(e, -1, 1));
-
-        dbg!(d);
-
-        loop {
-            let coord = (p[1] as usize, p[0] as usize);
-
-            map[coord] += 1;
-
-            if map[coord] > 1 {
-                multiple_overlaps.insert(p);
-            }
-
-            if p == line.1 {
-                break;
-            }
-
-            p += d;
-        }
-    }
+    println!("straight overlaps count: {}", count_overlaps_dense(straight.iter().copied()));
+    println!("multiple overlaps count: {}", count_overlaps_dense(with_diagonals.iter().copied()));
 
-    //dbg!(&map);
-    println!("{:?}", &map);
-    println!("multiple overlaps count: {}", multiple_overlaps.len());
+    println!("any slope sparse overlaps count: {}", count_overlaps_sparse(&lines));
+    println!("any slope sweep overlaps count: {}", count_overlaps_sweep(&lines));
 }
 
 #[cfg(test)]
@@ -121,7 +124,7 @@ mod test {
 
     use crate::{parse_lines, Line};
 
-    fn get_test_input() -> Vec<Line> {
+    pub(crate) fn get_test_input() -> Vec<Line> {
         let test_data_str = include_str!("../test_input.txt");
 
         let test_data_reader = Cursor::new(test_data_str.to_owned());
@@ -0,0 +1,304 @@
+use std::collections::{HashMap, HashSet};
+
+use ndarray::Array2;
+
+use crate::{Line, Point, Vec2};
+
+/// Draw every line onto a grid covering their bounding box
+pub fn count_overlaps_dense<'a>(lines: impl IntoIterator<Item = &'a Line> + Clone) -> usize {
+    let mut points = lines.clone().into_iter().flat_map(|line| [line.0, line.1]);
+
+    let first = match points.next() {
+        Some(first) => first,
+        None => return 0,
+    };
+
+    let (min, max) = points.fold((first, first), |(min, max), p| (min.inf(&p), max.sup(&p)));
+    let size = max - min + Vec2::new(1, 1);
+
+    let mut map: Array2<u32> = Array2::zeros((size[1] as usize, size[0] as usize));
+
+    for line in lines {
+        for p in line.points() {
+            let offset = p - min;
+            map[(offset[1] as usize, offset[0] as usize)] += 1;
+        }
+    }
+
+    map.iter().filter(|count| **count > 1).count()
+}
+
+/// Only store the points lines pass through so coordinates can be huge
+pub fn count_overlaps_sparse<'a>(lines: impl IntoIterator<Item = &'a Line>) -> usize {
+    let mut counts: HashMap<Point, u32> = HashMap::new();
+
+    for line in lines {
+        for p in line.points() {
+            *counts.entry(p).or_insert(0) += 1;
+        }
+    }
+
+    counts.values().filter(|count| **count > 1).count()
+}
+
+/// How many steps along its infinite line a point is, points on the same
+/// line all have the same remainder so this counts up one at a time
+fn position(step: Vec2, p: Point) -> i64 {
+    if step[0] != 0 {
+        (p[0] as i64).div_euclid(step[0] as i64)
+    } else {
+        (p[1] as i64).div_euclid(step[1] as i64)
+    }
+}
+
+/// All the lines lying along one infinite line with every point's position
+/// along it measured in steps
+struct SupportLine {
+    /// Normalized so x is positive, or y is if x is zero
+    step: Vec2,
+    /// step.y * x - step.x * y which is the same for every point on the line
+    offset: i64,
+    /// Ranges of steps covered by at least one line
+    covered: Vec<(i64, i64)>,
+    /// Ranges of steps covered by two or more lines
+    overlapped: Vec<(i64, i64)>,
+}
+
+impl SupportLine {
+    /// Whether a position along the line is within one of the ranges
+    fn within(ranges: &[(i64, i64)], pos: i64) -> bool {
+        let i = ranges.partition_point(|(_, end)| *end < pos);
+        i < ranges.len() && ranges[i].0 <= pos
+    }
+
+    /// The lattice point where two lines that aren't parallel cross, if any
+    fn crossing(&self, other: &SupportLine) -> Option<Point> {
+        let (a1, b1) = (self.step[0] as i128, self.step[1] as i128);
+        let (a2, b2) = (other.step[0] as i128, other.step[1] as i128);
+        let (c1, c2) = (self.offset as i128, other.offset as i128);
+
+        // Solve b1 * x - a1 * y = c1 and b2 * x - a2 * y = c2
+        let det = a1 * b2 - a2 * b1;
+        if det == 0 {
+            return None;
+        }
+
+        let x_num = a1 * c2 - a2 * c1;
+        let y_num = b1 * c2 - b2 * c1;
+
+        if x_num % det != 0 || y_num % det != 0 {
+            return None;
+        }
+
+        Some(Point::new(
+            i32::try_from(x_num / det).ok()?,
+            i32::try_from(y_num / det).ok()?,
+        ))
+    }
+}
+
+/// Sweep the start and end of each range in order and keep the ranges where
+/// at least `depth` of them are open
+fn ranges_with_depth(ranges: &[(i64, i64)], depth: usize) -> Vec<(i64, i64)> {
+    let mut events: Vec<(i64, i32)> = ranges
+        .iter()
+        .flat_map(|(start, end)| [(*start, 1), (end + 1, -1)])
+        .collect();
+    events.sort_unstable();
+
+    let mut merged = Vec::new();
+    let mut open = 0i32;
+    let mut range_start = None;
+
+    for (pos, change) in events {
+        open += change;
+
+        if open as usize >= depth {
+            range_start.get_or_insert(pos);
+        } else if let Some(start) = range_start.take() {
+            merged.push((start, pos - 1));
+        }
+    }
+
+    merged
+}
+
+/// Count points covered by two or more lines without visiting every point.
+///
+/// Lines are grouped by the infinite line they lie on and overlaps along each
+/// of those are found by sweeping their ends. Overlaps between different
+/// infinite lines can only happen where they cross, so each pair is checked
+/// for a lattice crossing that both cover.
+pub fn count_overlaps_sweep<'a>(lines: impl IntoIterator<Item = &'a Line>) -> usize {
+    let mut groups: HashMap<(Vec2, i64), Vec<(i64, i64)>> = HashMap::new();
+
+    for line in lines {
+        let mut step = line.step();
+
+        // A single point sits on any line through it
+        if step == Vec2::zeros() {
+            step = Vec2::new(1, 0);
+        }
+
+        if step[0] < 0 || (step[0] == 0 && step[1] < 0) {
+            step = -step;
+        }
+
+        let offset = step[1] as i64 * line.0[0] as i64 - step[0] as i64 * line.0[1] as i64;
+
+        let (start, end) = (position(step, line.0), position(step, line.1));
+        groups.entry((step, offset)).or_default().push((start.min(end), start.max(end)));
+    }
+
+    let support_lines: Vec<SupportLine> = groups
+        .into_iter()
+        .map(|((step, offset), ranges)| SupportLine {
+            step,
+            offset,
+            covered: ranges_with_depth(&ranges, 1),
+            overlapped: ranges_with_depth(&ranges, 2),
+        })
+        .collect();
+
+    let collinear_overlaps: i64 = support_lines
+        .iter()
+        .flat_map(|support| support.overlapped.iter())
+        .map(|(start, end)| end - start + 1)
+        .sum();
+
+    // Every support line covering each crossing point, a point can be where
+    // more than two of them cross
+    let mut crossings: HashMap<Point, HashSet<usize>> = HashMap::new();
+
+    for (i, a) in support_lines.iter().enumerate() {
+        for (j, b) in support_lines.iter().enumerate().skip(i + 1) {
+            let p = match a.crossing(b) {
+                Some(p) => p,
+                None => continue,
+            };
+
+            let (a_pos, b_pos) = (position(a.step, p), position(b.step, p));
+
+            if SupportLine::within(&a.covered, a_pos) && SupportLine::within(&b.covered, b_pos) {
+                crossings.entry(p).or_default().extend([i, j]);
+            }
+        }
+    }
+
+    // A crossing point counts once in total, but it was already counted once
+    // for each support line it's overlapped on
+    let crossing_adjustment: i64 = crossings
+        .iter()
+        .map(|(p, lines)| {
+            let overlapped_on = lines
+                .iter()
+                .filter(|line| {
+                    let support = &support_lines[**line];
+                    SupportLine::within(&support.overlapped, position(support.step, *p))
+                })
+                .count();
+
+            1 - overlapped_on as i64
+        })
+        .sum();
+
+    (collinear_overlaps + crossing_adjustment) as usize
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::{count_overlaps_dense, count_overlaps_sparse, count_overlaps_sweep};
+    use crate::test::get_test_input;
+    use crate::{Line, Point};
+
+    #[test]
+    fn test_example() {
+        let lines = get_test_input();
+
+        let straight: Vec<&Line> = lines.iter().filter(|l| l.horiz_or_vert()).collect();
+        assert_eq!(count_overlaps_dense(straight.iter().copied()), 5);
+        assert_eq!(count_overlaps_sparse(straight.iter().copied()), 5);
+        assert_eq!(count_overlaps_sweep(straight.iter().copied()), 5);
+
+        let with_diagonals: Vec<&Line> = lines.iter().filter(|l| l.horiz_or_vert() || l.diagional()).collect();
+        assert_eq!(count_overlaps_dense(with_diagonals.iter().copied()), 12);
+        assert_eq!(count_overlaps_sparse(with_diagonals.iter().copied()), 12);
+        assert_eq!(count_overlaps_sweep(with_diagonals.iter().copied()), 12);
+    }
+
+    #[test]
+    fn test_lattice_points() {
+        let line = Line(Point::new(1, 1), Point::new(7, -8));
+        let points: Vec<Point> = line.points().collect();
+
+        assert_eq!(points, vec![Point::new(1, 1), Point::new(3, -2), Point::new(5, -5), Point::new(7, -8)]);
+
+        let single = Line(Point::new(4, 4), Point::new(4, 4));
+        assert_eq!(single.points().count(), 1);
+    }
+
+    #[test]
+    fn test_huge_coordinates() {
+        let lines = vec![
+            Line(Point::new(-1_050_000_000, 0), Point::new(1_050_000_000, 0)),
+            Line(Point::new(1_000_000_000, -3), Point::new(1_000_000_000, 3)),
+            Line(Point::new(-1_000_000_003, -1), Point::new(-999_999_997, 1)),
+            Line(Point::new(-1_000_000_003, -1), Point::new(-999_999_997, 1)),
+        ];
+
+        // Two crossings with the horizontal line plus the three shared points of the repeated line
+        assert_eq!(count_overlaps_sweep(&lines), 4);
+        assert_eq!(count_overlaps_sparse(&lines[1..]), 3);
+    }
+
+    #[test]
+    fn test_crossing_overlapped_runs() {
+        // The middle point is overlapped along both support lines but only counts once
+        let lines = vec![
+            Line(Point::new(0, 0), Point::new(4, 0)),
+            Line(Point::new(0, 0), Point::new(4, 0)),
+            Line(Point::new(2, -2), Point::new(2, 2)),
+            Line(Point::new(2, -2), Point::new(2, 2)),
+        ];
+
+        assert_eq!(count_overlaps_dense(&lines), 9);
+        assert_eq!(count_overlaps_sparse(&lines), 9);
+        assert_eq!(count_overlaps_sweep(&lines), 9);
+
+        // Three support lines through one point, overlapped on one of them
+        let lines = vec![
+            Line(Point::new(0, 0), Point::new(4, 0)),
+            Line(Point::new(0, 0), Point::new(4, 0)),
+            Line(Point::new(2, -2), Point::new(2, 2)),
+            Line(Point::new(0, -2), Point::new(4, 2)),
+        ];
+
+        assert_eq!(count_overlaps_sweep(&lines), count_overlaps_dense(&lines));
+    }
+
+    prop_compose! {
+        fn line_strategy()(x0 in -12..12, y0 in -12..12, x1 in -12..12, y1 in -12..12) -> Line {
+            Line(Point::new(x0, y0), Point::new(x1, y1))
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_counters_agree(lines in prop::collection::vec(line_strategy(), 0..25)) {
+            let dense = count_overlaps_dense(&lines);
+
+            assert_eq!(count_overlaps_sparse(&lines), dense);
+            assert_eq!(count_overlaps_sweep(&lines), dense);
+        }
+
+        #[test]
+        fn test_counters_agree_with_repeats(lines in prop::collection::vec(line_strategy(), 0..12)) {
+            // Every line twice so most crossings are overlapped on both lines
+            let lines: Vec<Line> = lines.iter().chain(lines.iter()).map(|line| Line(line.0, line.1)).collect();
+
+            assert_eq!(count_overlaps_sweep(&lines), count_overlaps_dense(&lines));
+        }
+    }
+}
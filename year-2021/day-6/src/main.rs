@@ -1,6 +1,135 @@
 use std::io::{self, BufRead};
 
-type FishCounts = Vec<usize>;
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+/// Number of fish with each timer value, index is the timer
+type FishCounts = Vec<u128>;
+
+type Matrix = Vec<Vec<BigUint>>;
+
+/// How long a fish waits between spawning, a timer of 0 spawns on the next day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LanternfishModel {
+    /// Timer a fish resets to after spawning
+    spawn_timer: usize,
+    /// Timer a newly spawned fish starts with
+    newborn_timer: usize,
+}
+
+impl LanternfishModel {
+    const PUZZLE: LanternfishModel = LanternfishModel {
+        spawn_timer: 6,
+        newborn_timer: 8,
+    };
+
+    fn timer_count(&self) -> usize {
+        usize::max(self.spawn_timer, self.newborn_timer) + 1
+    }
+
+    fn count_timers(&self, fish: &[usize]) -> FishCounts {
+        let mut fish_count_by_tts = vec![0; self.timer_count()];
+
+        for fish_tts in fish.iter().copied() {
+            assert!(fish_tts < fish_count_by_tts.len(), "Fish timer {} too high", fish_tts);
+            fish_count_by_tts[fish_tts] += 1;
+        }
+
+        fish_count_by_tts
+    }
+
+    /// Rotate the timer counts one day at a time, None on overflow
+    fn simulate(&self, fish: &[usize], days: usize) -> Option<u128> {
+        let mut fish_count_by_tts = self.count_timers(fish);
+
+        for _day in 0..days {
+            let spawns = fish_count_by_tts[0];
+
+            fish_count_by_tts.rotate_left(1);
+            *fish_count_by_tts.last_mut().unwrap() = 0;
+
+            fish_count_by_tts[self.spawn_timer] = fish_count_by_tts[self.spawn_timer].checked_add(spawns)?;
+            fish_count_by_tts[self.newborn_timer] = fish_count_by_tts[self.newborn_timer].checked_add(spawns)?;
+        }
+
+        fish_count_by_tts
+            .iter()
+            .try_fold(0u128, |total, count| total.checked_add(*count))
+    }
+
+    /// transitions[to][from] is how many fish with timer to one fish with timer from becomes after a day
+    fn transitions(&self) -> Matrix {
+        let size = self.timer_count();
+        let mut transitions = vec![vec![BigUint::zero(); size]; size];
+
+        for from in 1..size {
+            transitions[from - 1][from] = BigUint::from(1u64);
+        }
+
+        transitions[self.spawn_timer][0] += &BigUint::from(1u64);
+        transitions[self.newborn_timer][0] += &BigUint::from(1u64);
+
+        transitions
+    }
+
+    /// Population after any number of days using the transition matrix raised to days
+    fn population(&self, fish: &[usize], days: u64) -> BigUint {
+        let counts: Vec<BigUint> = self
+            .count_timers(fish)
+            .into_iter()
+            .map(|count| BigUint::from(count as u64))
+            .collect();
+
+        let power = matrix_pow(&self.transitions(), days);
+
+        power
+            .iter()
+            .flat_map(|row| row.iter().zip(counts.iter()).map(|(factor, count)| factor * count))
+            .sum()
+    }
+}
+
+fn matrix_mul(a: &Matrix, b: &Matrix) -> Matrix {
+    let size = a.len();
+    let mut out = vec![vec![BigUint::zero(); size]; size];
+
+    for row in 0..size {
+        for k in 0..size {
+            if a[row][k].is_zero() {
+                continue;
+            }
+
+            for col in 0..size {
+                let product = &a[row][k] * &b[k][col];
+                out[row][col] += &product;
+            }
+        }
+    }
+
+    out
+}
+
+fn matrix_pow(matrix: &Matrix, mut exp: u64) -> Matrix {
+    let size = matrix.len();
+    let mut result: Matrix = (0..size)
+        .map(|row| (0..size).map(|col| BigUint::from((row == col) as u64)).collect())
+        .collect();
+    let mut base = matrix.clone();
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = matrix_mul(&result, &base);
+        }
+
+        exp >>= 1;
+
+        if exp > 0 {
+            base = matrix_mul(&base, &base);
+        }
+    }
+
+    result
+}
 
 fn parse_fishies(mut reader: impl BufRead) -> Vec<usize> {
     let mut counts_str = String::new();
@@ -19,39 +148,25 @@ fn main() {
         parse_fishies(stdin_lock)
     };
 
-    let mut fish_count_by_tts: [usize; 9] = [0; 9];
-
-    for fish_tts in fish.into_iter() {
-        fish_count_by_tts[fish_tts] += 1;
-    }
+    let model = LanternfishModel::PUZZLE;
 
-    dbg!(fish_count_by_tts);
+    dbg!(model.count_timers(&fish));
 
-    for day in 0..256 {
-        let mut new_fish_count_by_tts: [usize; 9] = [0; 9];
-        let spawns = fish_count_by_tts[0];
+    println!("total fish (simulated): {:?}", model.simulate(&fish, 256));
 
-        new_fish_count_by_tts[6] = spawns;
-        new_fish_count_by_tts[8] = spawns;
-
-        for i in 1..9 {
-            new_fish_count_by_tts[i - 1] += fish_count_by_tts[i];
-        }
-
-        fish_count_by_tts = new_fish_count_by_tts;
-        dbg!(fish_count_by_tts);
+    for days in [80, 256, 10_000] {
+        println!("total fish after {} days: {}", days, model.population(&fish, days));
     }
-
-    let total_fish: usize = fish_count_by_tts.iter().copied().sum();
-
-    println!("total fish: {}", total_fish);
 }
 
 #[cfg(test)]
 mod test {
     use std::io::Cursor;
 
-    use crate::parse_fishies;
+    use num_bigint::BigUint;
+    use num_traits::ToPrimitive;
+
+    use crate::{parse_fishies, LanternfishModel};
 
     fn get_test_input() -> Vec<usize> {
         let test_data_str = include_str!("../test_input.txt");
@@ -67,4 +182,64 @@ mod test {
 
         assert_eq!(fish, vec![3, 4, 3, 1, 2]);
     }
+
+    #[test]
+    fn test_example() {
+        let fish = get_test_input();
+        let model = LanternfishModel::PUZZLE;
+
+        assert_eq!(model.simulate(&fish, 18), Some(26));
+        assert_eq!(model.simulate(&fish, 80), Some(5934));
+        assert_eq!(model.population(&fish, 80).to_u128(), Some(5934));
+        assert_eq!(model.population(&fish, 256).to_u128(), Some(26984457539));
+    }
+
+    #[test]
+    fn test_matrix_matches_simulation() {
+        let fish = get_test_input();
+
+        let models = [
+            LanternfishModel::PUZZLE,
+            LanternfishModel { spawn_timer: 4, newborn_timer: 4 },
+            LanternfishModel { spawn_timer: 7, newborn_timer: 3 },
+        ];
+
+        for model in models {
+            for days in 0..120 {
+                assert_eq!(
+                    model.population(&fish, days as u64).to_u128(),
+                    model.simulate(&fish, days),
+                    "{:?} after {} days",
+                    model,
+                    days
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_huge_day_count() {
+        let fish = get_test_input();
+        let model = LanternfishModel::PUZZLE;
+
+        assert_eq!(model.simulate(&fish, 2000), None);
+
+        // Follow the rotation with big integers to check the matrix power
+        let mut counts: Vec<BigUint> = model
+            .count_timers(&fish)
+            .into_iter()
+            .map(|count| BigUint::from(count as u64))
+            .collect();
+
+        for _day in 0..2000 {
+            let spawns = counts[0].clone();
+            counts.rotate_left(1);
+            counts[8] = spawns.clone();
+            counts[6] += &spawns;
+        }
+
+        let expected: BigUint = counts.iter().sum();
+
+        assert_eq!(model.population(&fish, 2000), expected);
+    }
 }
@@ -1,4 +1,5 @@
 use std::io::Cursor;
+use day_7::cost::{brute_force, ternary_search, FuelCost, Linear, Quadratic, Triangular};
 use day_7::{brute_force_find_best, find_with_local_minima, parse_input, Input};
 use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId};
 use rand::{Rng, SeedableRng};
@@ -60,7 +61,35 @@ fn synthetic_large(c: &mut Criterion) {
     group.finish();
 }
 
+fn cost_functions(c: &mut Criterion) {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0x3f1c2a9d57e0b846);
+
+    let test_input: Vec<i32> = (0..10000).map(|_| rng.gen_range(0..2000)).collect();
+
+    let costs: [(&str, &dyn FuelCost); 3] = [
+        ("linear", &Linear),
+        ("triangular", &Triangular),
+        ("quadratic", &Quadratic),
+    ];
+
+    for (name, cost) in costs {
+        let mut group = c.benchmark_group(format!("cost_{}", name));
+        for size in [100usize, 1000, 10000].iter() {
+            group.bench_with_input(BenchmarkId::new("brute_force", size), size, |b, &size| {
+                b.iter(|| brute_force(cost, black_box(&test_input[..size])));
+            });
+            group.bench_with_input(BenchmarkId::new("ternary_search", size), size, |b, &size| {
+                b.iter(|| ternary_search(cost, black_box(&test_input[..size])));
+            });
+            group.bench_with_input(BenchmarkId::new("minimize", size), size, |b, &size| {
+                b.iter(|| cost.minimize(black_box(&test_input[..size])));
+            });
+        }
+        group.finish();
+    }
+}
+
 
-criterion_group!(benches, synthetic_large, test_input, full_problem_input);
+criterion_group!(benches, synthetic_large, cost_functions, test_input, full_problem_input);
 criterion_main!(benches);
 
@@ -0,0 +1,231 @@
+use std::collections::BTreeMap;
+
+/// How much fuel a crab burns to move a number of steps
+///
+/// Costs must never decrease as the distance grows and the total over all crabs
+/// is assumed to be convex, which holds for any convex per-crab cost.
+pub trait FuelCost {
+    fn fuel(&self, distance: u32) -> u64;
+
+    /// Every position the crabs can align on for the least fuel
+    fn minimize(&self, crabs: &[i32]) -> Option<Alignment> {
+        ternary_search(self, crabs)
+    }
+}
+
+/// One fuel per step, part 1
+pub struct Linear;
+
+/// Each step costs one more than the last, part 2
+pub struct Triangular;
+
+/// Distance squared
+pub struct Quadratic;
+
+/// Any other cost function, the total must still be convex for `minimize` to be exact
+pub struct Custom<F>(pub F);
+
+impl FuelCost for Linear {
+    fn fuel(&self, distance: u32) -> u64 {
+        distance as u64
+    }
+
+    fn minimize(&self, crabs: &[i32]) -> Option<Alignment> {
+        let (lower, upper) = weighted_median(crabs)?;
+
+        Some(Alignment {
+            positions: (lower..=upper).collect(),
+            cost: total_fuel(self, crabs, lower),
+        })
+    }
+}
+
+impl FuelCost for Triangular {
+    fn fuel(&self, distance: u32) -> u64 {
+        let d = distance as u64;
+        d * (1 + d) / 2
+    }
+}
+
+impl FuelCost for Quadratic {
+    fn fuel(&self, distance: u32) -> u64 {
+        let d = distance as u64;
+        d * d
+    }
+}
+
+impl<F: Fn(u32) -> u64> FuelCost for Custom<F> {
+    fn fuel(&self, distance: u32) -> u64 {
+        (self.0)(distance)
+    }
+}
+
+/// Optimal positions in ascending order and the fuel they all cost
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alignment {
+    pub positions: Vec<i32>,
+    pub cost: u64,
+}
+
+pub fn total_fuel<C: FuelCost + ?Sized>(cost: &C, crabs: &[i32], pos: i32) -> u64 {
+    crabs
+        .iter()
+        .map(|c| cost.fuel(c.abs_diff(pos)))
+        .sum()
+}
+
+/// Check every position between the outermost crabs
+pub fn brute_force<C: FuelCost + ?Sized>(cost: &C, crabs: &[i32]) -> Option<Alignment> {
+    let min = crabs.iter().copied().min()?;
+    let max = crabs.iter().copied().max()?;
+
+    let costs: Vec<(i32, u64)> = (min..=max)
+        .map(|pos| (pos, total_fuel(cost, crabs, pos)))
+        .collect();
+
+    let best = costs.iter().map(|(_, fuel)| *fuel).min()?;
+
+    Some(Alignment {
+        positions: costs
+            .into_iter()
+            .filter(|(_, fuel)| *fuel == best)
+            .map(|(pos, _)| pos)
+            .collect(),
+        cost: best,
+    })
+}
+
+/// The lowest and highest positions with at least half the crabs on either side,
+/// crabs sharing a position count as a single weighted point.
+///
+/// With a linear cost moving anywhere between them costs the same since as many
+/// crabs get closer as get further away.
+fn weighted_median(crabs: &[i32]) -> Option<(i32, i32)> {
+    let mut weights: BTreeMap<i32, usize> = BTreeMap::new();
+    for crab in crabs {
+        *weights.entry(*crab).or_insert(0) += 1;
+    }
+
+    let total = crabs.len();
+    let mut below = 0;
+    let mut lower = None;
+
+    for (pos, weight) in weights {
+        if lower.is_none() && 2 * (below + weight) >= total {
+            // Exactly half the weight up to here so the plateau runs to the next position
+            if 2 * (below + weight) > total {
+                return Some((pos, pos));
+            }
+
+            lower = Some(pos);
+        } else if let Some(lower) = lower {
+            return Some((lower, pos));
+        }
+
+        below += weight;
+    }
+
+    None
+}
+
+/// Narrow down on a minimum by comparing thirds of the range then widen out to
+/// the whole plateau of positions sharing its cost
+pub fn ternary_search<C: FuelCost + ?Sized>(cost: &C, crabs: &[i32]) -> Option<Alignment> {
+    let mut lo = crabs.iter().copied().min()?;
+    let mut hi = crabs.iter().copied().max()?;
+
+    while hi - lo > 2 {
+        let third = (hi - lo) / 3;
+        let m1 = lo + third;
+        let m2 = hi - third;
+
+        let f1 = total_fuel(cost, crabs, m1);
+        let f2 = total_fuel(cost, crabs, m2);
+
+        if f1 < f2 {
+            hi = m2 - 1;
+        } else if f1 > f2 {
+            lo = m1 + 1;
+        } else {
+            // Convex so nothing outside can do better than the two ends
+            lo = m1;
+            hi = m2;
+        }
+    }
+
+    let (best_pos, best) = (lo..=hi)
+        .map(|pos| (pos, total_fuel(cost, crabs, pos)))
+        .min_by_key(|(_, fuel)| *fuel)?;
+
+    let min = crabs.iter().copied().min()?;
+    let max = crabs.iter().copied().max()?;
+
+    // The cost can only climb moving away from a minimum so the ends of the
+    // plateau are where it first changes
+    let first = partition_point(min, best_pos, |pos| total_fuel(cost, crabs, pos) > best);
+    let last = partition_point(best_pos, max + 1, |pos| total_fuel(cost, crabs, pos) == best) - 1;
+
+    Some(Alignment {
+        positions: (first..=last).collect(),
+        cost: best,
+    })
+}
+
+/// First position in lo..hi where pred is false, pred must be true then false
+fn partition_point(mut lo: i32, mut hi: i32, pred: impl Fn(i32) -> bool) -> i32 {
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+
+        if pred(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+
+    lo
+}
+
+#[cfg(test)]
+mod test {
+    use super::{brute_force, ternary_search, Custom, FuelCost, Linear, Quadratic, Triangular};
+
+    const EXAMPLE: [i32; 10] = [16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+
+    #[test]
+    fn test_example() {
+        let linear = Linear.minimize(&EXAMPLE).unwrap();
+        assert_eq!(linear.positions, vec![2]);
+        assert_eq!(linear.cost, 37);
+
+        let triangular = Triangular.minimize(&EXAMPLE).unwrap();
+        assert_eq!(triangular.positions, vec![5]);
+        assert_eq!(triangular.cost, 168);
+    }
+
+    #[test]
+    fn test_empty_crabs() {
+        assert_eq!(Linear.minimize(&[]), None);
+        assert_eq!(Quadratic.minimize(&[]), None);
+        assert_eq!(brute_force(&Triangular, &[]), None);
+    }
+
+    #[test]
+    fn test_all_optimal_positions() {
+        // Anywhere between two crabs costs the same distance
+        let linear = Linear.minimize(&[1, 5]).unwrap();
+        assert_eq!(linear.positions, vec![1, 2, 3, 4, 5]);
+        assert_eq!(linear.cost, 4);
+
+        // Two equally good positions either side of the midpoint
+        let quadratic = Quadratic.minimize(&[0, 3]).unwrap();
+        assert_eq!(quadratic.positions, vec![1, 2]);
+        assert_eq!(quadratic.cost, 5);
+
+        // Moving is free up to 3 steps away
+        let free_nearby = Custom(|d: u32| d.saturating_sub(3) as u64);
+        let alignment = ternary_search(&free_nearby, &[0, 10]).unwrap();
+        assert_eq!(alignment, brute_force(&free_nearby, &[0, 10]).unwrap());
+        assert_eq!(alignment.positions, vec![3, 4, 5, 6, 7]);
+    }
+}
@@ -1,5 +1,9 @@
 use std::io::{self, BufRead};
 
+pub mod cost;
+
+use cost::{FuelCost, Triangular};
+
 pub type Input = Vec<i32>;
 
 pub fn parse_input(mut reader: impl BufRead) -> Input {
@@ -17,9 +21,7 @@ pub fn parse_input(mut reader: impl BufRead) -> Input {
 // }
 
 fn fuel_cost(d: i32) -> i32 {
-    //(1..(d+1)).sum()
-
-    d * (1 + d) / 2
+    Triangular.fuel(d as u32) as i32
 }
 
 pub fn crab_align_cost(crabs: &[i32], pos: i32) -> i32 {
@@ -154,6 +156,7 @@ mod test {
     use proptest::prelude::*;
     use std::io::Cursor;
 
+    use crate::cost::{brute_force, ternary_search, Custom, FuelCost, Linear, Quadratic, Triangular};
     use crate::{brute_force_find_best, find_with_local_minima, parse_input, Input};
 
     fn get_test_input() -> Input {
@@ -221,5 +224,35 @@ mod test {
 
             assert_eq!(maybe_minima_best.map(|b|b.1), maybe_best.map(|b|b.1));
         }
+
+        #[test]
+        fn test_weighted_median(crabs in crabs_strategy(1000)) {
+            assert_eq!(Linear.minimize(&crabs), brute_force(&Linear, &crabs));
+        }
+
+        #[test]
+        fn test_ternary_search(crabs in crabs_strategy(1000)) {
+            assert_eq!(ternary_search(&Linear, &crabs), brute_force(&Linear, &crabs));
+            assert_eq!(Triangular.minimize(&crabs), brute_force(&Triangular, &crabs));
+            assert_eq!(Quadratic.minimize(&crabs), brute_force(&Quadratic, &crabs));
+        }
+
+        #[test]
+        fn test_custom_cost(crabs in crabs_strategy(200), free_steps in 0u32..50) {
+            let cost = Custom(|d: u32| {
+                let d = d.saturating_sub(free_steps) as u64;
+                d * d * d
+            });
+
+            assert_eq!(cost.minimize(&crabs), brute_force(&cost, &crabs));
+        }
+
+        #[test]
+        fn test_matches_part2_cost(crabs in crabs_strategy(1000)) {
+            let maybe_best = brute_force_find_best(&crabs);
+            let maybe_exact_best = Triangular.minimize(&crabs);
+
+            assert_eq!(maybe_exact_best.map(|b| b.cost as i32), maybe_best.map(|b| b.1));
+        }
     }
 }
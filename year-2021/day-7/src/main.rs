@@ -1,5 +1,6 @@
 use std::io;
 
+use day_7::cost::{FuelCost, Linear, Triangular};
 use day_7::{parse_input, brute_force_find_best, find_with_local_minima};
 
 
@@ -29,4 +30,13 @@ fn main() {
             maybe_minima_best.0, maybe_minima_best.1
         );
     }
+
+    for (name, cost) in [("linear", &Linear as &dyn FuelCost), ("triangular", &Triangular)] {
+        if let Some(alignment) = cost.minimize(&crab_positions) {
+            println!(
+                "exact {} best positions: {:?} cost: {}",
+                name, alignment.positions, alignment.cost
+            );
+        }
+    }
 }
@@ -0,0 +1,506 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Bit n is set for segment (or wire) letter 'a' + n
+pub type Mask = u32;
+
+/// The standard display, each line is a symbol followed by the segments it lights
+pub const SEVEN_SEGMENT: &str = "
+0 abcefg
+1 cf
+2 acdeg
+3 acdfg
+4 bcdf
+5 abdfg
+6 abdefg
+7 acf
+8 abcdefg
+9 abcdfg
+";
+
+/// Give up listing wirings for an ambiguous line after this many
+const MAX_WIRINGS: usize = 16;
+
+fn parse_mask(letters: &str, segment_count: usize) -> Result<Mask, String> {
+    let mut mask = 0;
+
+    for ch in letters.chars() {
+        let index = (ch as u32).wrapping_sub('a' as u32);
+
+        if !ch.is_ascii_lowercase() || index as usize >= segment_count {
+            return Err(format!("invalid segment {:?} in {:?}", ch, letters));
+        }
+
+        mask |= 1 << index;
+    }
+
+    Ok(mask)
+}
+
+fn mask_letters(mask: Mask) -> String {
+    (0..Mask::BITS)
+        .filter(|bit| mask >> bit & 1 == 1)
+        .map(|bit| (b'a' + bit as u8) as char)
+        .collect()
+}
+
+/// Which segments light up for each symbol a display can show
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentTable {
+    segment_count: usize,
+    symbols: Vec<(char, Mask)>,
+}
+
+impl SegmentTable {
+    pub fn seven_segment() -> Self {
+        SEVEN_SEGMENT.parse().unwrap()
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.segment_count
+    }
+
+    pub fn symbols(&self) -> &[(char, Mask)] {
+        &self.symbols
+    }
+
+    fn full_mask(&self) -> Mask {
+        (1 << self.segment_count) - 1
+    }
+
+    pub fn symbol(&self, segments: Mask) -> Option<char> {
+        self.symbols
+            .iter()
+            .find(|(_, mask)| *mask == segments)
+            .map(|(symbol, _)| *symbol)
+    }
+}
+
+impl FromStr for SegmentTable {
+    type Err = String;
+
+    /// One symbol per line followed by its segment letters, the display has
+    /// as many segments as the highest letter used
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries = Vec::new();
+
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (symbol, letters) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| format!("expected a symbol and its segments: {:?}", line))?;
+
+            let mut symbol_chars = symbol.chars();
+            match (symbol_chars.next(), symbol_chars.next()) {
+                (Some(symbol), None) => entries.push((symbol, letters.trim())),
+                _ => return Err(format!("symbol must be a single character: {:?}", symbol)),
+            }
+        }
+
+        let segment_count = entries
+            .iter()
+            .flat_map(|(_, letters)| letters.chars())
+            .map(|ch| (ch as usize).saturating_sub('a' as usize) + 1)
+            .max()
+            .ok_or("empty segment table")?;
+
+        if segment_count > 26 {
+            return Err("segments must be lowercase letters".to_string());
+        }
+
+        let mut symbols: Vec<(char, Mask)> = Vec::new();
+
+        for (symbol, letters) in entries {
+            let mask = parse_mask(letters, segment_count)?;
+
+            if symbols.iter().any(|(other, _)| *other == symbol) {
+                return Err(format!("duplicate symbol {:?}", symbol));
+            }
+
+            if let Some((other, _)) = symbols.iter().find(|(_, other_mask)| *other_mask == mask) {
+                return Err(format!("segments {:?} already used by {:?}", letters, other));
+            }
+
+            symbols.push((symbol, mask));
+        }
+
+        Ok(SegmentTable {
+            segment_count,
+            symbols,
+        })
+    }
+}
+
+/// The segment each wire is connected to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wiring(Vec<u8>);
+
+impl Wiring {
+    /// Segments lit by a set of wires
+    pub fn apply(&self, wires: Mask) -> Mask {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(wire, _)| wires >> wire & 1 == 1)
+            .fold(0, |segments, (_, segment)| segments | 1 << segment)
+    }
+}
+
+impl fmt::Display for Wiring {
+    /// The segment letter for wire a, then b, ...
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in self.0.iter() {
+            write!(f, "{}", (b'a' + segment) as char)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    InvalidPattern(String),
+    /// No wiring fits every pattern, these are the segments each wire could
+    /// still have been connected to when that became clear
+    Contradiction { candidates: Vec<Mask> },
+    /// More than one wiring fits, lists at most MAX_WIRINGS of them
+    Ambiguous { wirings: Vec<Wiring> },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidPattern(err) => write!(f, "invalid pattern: {}", err),
+            DecodeError::Contradiction { candidates } => {
+                write!(f, "contradiction, surviving candidates:")?;
+
+                for (wire, segments) in candidates.iter().enumerate() {
+                    write!(f, " {}:{}", (b'a' + wire as u8) as char, mask_letters(*segments))?;
+                }
+
+                Ok(())
+            }
+            DecodeError::Ambiguous { wirings } => {
+                write!(f, "ambiguous, {} wirings fit:", wirings.len())?;
+
+                for wiring in wirings {
+                    write!(f, " {}", wiring)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+struct Solver<'a> {
+    table: &'a SegmentTable,
+    patterns: Vec<Mask>,
+}
+
+impl<'a> Solver<'a> {
+    fn wires(&self) -> std::ops::Range<usize> {
+        0..self.table.segment_count()
+    }
+
+    /// Narrow down the segments each wire can go to until nothing changes,
+    /// returns false as soon as a wire or pattern has no options left leaving
+    /// the candidates as they were at the start of that round
+    fn propagate(&self, candidates: &mut [Mask]) -> bool {
+        let full = self.table.full_mask();
+
+        loop {
+            let before = candidates.to_vec();
+
+            // Each pattern is one of the symbols with as many segments whose lit
+            // segments can all be reached by its wires and the rest by the others
+            for pattern in self.patterns.iter().copied() {
+                let mut lit = 0;
+                let mut unlit = 0;
+
+                for (_, segments) in self.table.symbols().iter().copied() {
+                    let fits = segments.count_ones() == pattern.count_ones()
+                        && self.wires().all(|wire| {
+                            let allowed = if pattern >> wire & 1 == 1 {
+                                segments
+                            } else {
+                                full & !segments
+                            };
+
+                            candidates[wire] & allowed != 0
+                        });
+
+                    if fits {
+                        lit |= segments;
+                        unlit |= full & !segments;
+                    }
+                }
+
+                for wire in self.wires() {
+                    candidates[wire] &= if pattern >> wire & 1 == 1 { lit } else { unlit };
+                }
+            }
+
+            // Wires and segments pair up one to one
+            for wire in self.wires() {
+                if candidates[wire].count_ones() == 1 {
+                    let solved = candidates[wire];
+
+                    for other in self.wires().filter(|other| *other != wire) {
+                        candidates[other] &= !solved;
+                    }
+                }
+            }
+
+            for segment in self.wires() {
+                let mut holders = self.wires().filter(|wire| candidates[*wire] >> segment & 1 == 1);
+
+                match (holders.next(), holders.next()) {
+                    (None, _) => {
+                        candidates.copy_from_slice(&before);
+                        return false;
+                    }
+                    (Some(wire), None) => candidates[wire] = 1 << segment,
+                    _ => {}
+                }
+            }
+
+            if candidates.contains(&0) {
+                candidates.copy_from_slice(&before);
+                return false;
+            }
+
+            if candidates == before.as_slice() {
+                return true;
+            }
+        }
+    }
+
+    /// Guess the wire with the fewest options and propagate from there
+    fn search(&self, mut candidates: Vec<Mask>, found: &mut Vec<Wiring>) {
+        if found.len() >= MAX_WIRINGS || !self.propagate(&mut candidates) {
+            return;
+        }
+
+        let guess = self
+            .wires()
+            .filter(|wire| candidates[*wire].count_ones() > 1)
+            .min_by_key(|wire| candidates[*wire].count_ones());
+
+        match guess {
+            None => {
+                let wiring = Wiring(candidates.iter().map(|c| c.trailing_zeros() as u8).collect());
+
+                if self
+                    .patterns
+                    .iter()
+                    .all(|pattern| self.table.symbol(wiring.apply(*pattern)).is_some())
+                {
+                    found.push(wiring);
+                }
+            }
+            Some(wire) => {
+                for segment in self.wires().filter(|segment| candidates[wire] >> segment & 1 == 1) {
+                    let mut guessed = candidates.clone();
+                    guessed[wire] = 1 << segment;
+
+                    self.search(guessed, found);
+                }
+            }
+        }
+    }
+}
+
+/// Find the only wiring that turns every pattern into a symbol from the table
+pub fn solve_wiring<S: AsRef<str>>(table: &SegmentTable, patterns: &[S]) -> Result<Wiring, DecodeError> {
+    let patterns = patterns
+        .iter()
+        .map(|pattern| parse_mask(pattern.as_ref(), table.segment_count))
+        .collect::<Result<Vec<Mask>, String>>()
+        .map_err(DecodeError::InvalidPattern)?;
+
+    let solver = Solver { table, patterns };
+
+    let mut candidates = vec![table.full_mask(); table.segment_count];
+    let mut found = Vec::new();
+
+    if solver.propagate(&mut candidates) {
+        solver.search(candidates.clone(), &mut found);
+    }
+
+    match found.len() {
+        0 => Err(DecodeError::Contradiction { candidates }),
+        1 => Ok(found.remove(0)),
+        _ => Err(DecodeError::Ambiguous { wirings: found }),
+    }
+}
+
+fn decode_outputs<S: AsRef<str>>(table: &SegmentTable, wiring: &Wiring, outputs: &[S]) -> Result<String, DecodeError> {
+    outputs
+        .iter()
+        .map(|output| {
+            let wires = parse_mask(output.as_ref(), table.segment_count).map_err(DecodeError::InvalidPattern)?;
+
+            Ok(table.symbol(wiring.apply(wires)).expect("Solved wiring decodes every pattern"))
+        })
+        .collect()
+}
+
+/// Solve the wiring from every pattern on the line and read off the outputs.
+/// A line that fits more than one wiring still decodes if every wiring reads
+/// the outputs the same way.
+pub fn decode_entry<S: AsRef<str>>(
+    table: &SegmentTable,
+    preamble: &[S],
+    outputs: &[S],
+) -> Result<String, DecodeError> {
+    let all_patterns: Vec<&str> = preamble.iter().chain(outputs).map(|s| s.as_ref()).collect();
+
+    match solve_wiring(table, &all_patterns) {
+        Ok(wiring) => decode_outputs(table, &wiring, outputs),
+        Err(DecodeError::Ambiguous { wirings }) if wirings.len() < MAX_WIRINGS => {
+            let decoded = decode_outputs(table, &wirings[0], outputs)?;
+
+            for wiring in wirings.iter().skip(1) {
+                if decode_outputs(table, wiring, outputs)? != decoded {
+                    return Err(DecodeError::Ambiguous { wirings });
+                }
+            }
+
+            Ok(decoded)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode_entry, solve_wiring, DecodeError, SegmentTable, Wiring};
+    use crate::test::get_test_input;
+
+    #[test]
+    fn test_seven_segment_table() {
+        let table = SegmentTable::seven_segment();
+
+        assert_eq!(table.segment_count(), 7);
+        assert_eq!(table.symbols().len(), 10);
+        assert_eq!(table.symbol(0b1111111), Some('8'));
+        assert_eq!(table.symbol(0b0100100), Some('1'));
+        assert_eq!(table.symbol(0b0000011), None);
+    }
+
+    #[test]
+    fn test_example() {
+        let table = SegmentTable::seven_segment();
+
+        let decoded: Vec<String> = get_test_input()
+            .iter()
+            .map(|(preamble, outputs)| decode_entry(&table, preamble, outputs).unwrap())
+            .collect();
+
+        assert_eq!(decoded[0], "8394");
+        assert_eq!(decoded.iter().map(|s| s.parse::<u32>().unwrap()).sum::<u32>(), 61229);
+    }
+
+    #[test]
+    fn test_without_unique_lengths() {
+        let table = SegmentTable::seven_segment();
+
+        // The single line example with 1, 4, 7 and 8 left out
+        let preamble = ["cdfbe", "gcdfa", "fbcad", "cefabd", "cdfgeb", "cagedb"];
+        let outputs = ["cdfeb", "fcadb", "cdfeb", "cdbaf"];
+
+        // Every remaining digit lights both the top and bottom segment so
+        // their wires can't be told apart but the outputs still can
+        let all_patterns: Vec<&str> = preamble.iter().chain(outputs.iter()).copied().collect();
+        match solve_wiring(&table, &all_patterns) {
+            Err(DecodeError::Ambiguous { wirings }) => assert_eq!(wirings.len(), 2),
+            other => panic!("expected ambiguity, got {:?}", other),
+        }
+
+        assert_eq!(decode_entry(&table, &preamble, &outputs).unwrap(), "5353");
+    }
+
+    #[test]
+    fn test_contradiction() {
+        let table = SegmentTable::seven_segment();
+
+        // Only 1 has two segments so both can't be a 1
+        match solve_wiring(&table, &["ab", "cd"]) {
+            Err(DecodeError::Contradiction { candidates }) => {
+                assert_eq!(candidates.len(), 7);
+                assert!(!candidates.contains(&0));
+            }
+            other => panic!("expected a contradiction, got {:?}", other),
+        }
+
+        let err = solve_wiring(&table, &["abcdefgh"]).unwrap_err();
+        assert!(matches!(err, DecodeError::InvalidPattern(_)));
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        let table = SegmentTable::seven_segment();
+
+        // a and b are c and f in some order
+        match solve_wiring(&table, &["ab", "abc", "abde", "abcdefg"]) {
+            Err(DecodeError::Ambiguous { wirings }) => {
+                assert_eq!(wirings.len(), 8);
+                assert!(wirings.iter().all(|w| w.apply(0b11) == 0b100100));
+            }
+            other => panic!("expected ambiguity, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_table_from_data() {
+        assert_eq!(
+            "0 abc\n1 abc".parse::<SegmentTable>(),
+            Err("segments \"abc\" already used by '0'".to_string())
+        );
+        assert_eq!(
+            "0 abc\n0 ab".parse::<SegmentTable>(),
+            Err("duplicate symbol '0'".to_string())
+        );
+        assert!("10 ab".parse::<SegmentTable>().is_err());
+        assert!("X aB".parse::<SegmentTable>().is_err());
+
+        // Pseudo random glyphs on a 14 segment display
+        let mut seed = 0x2545f491u32;
+        let mut glyphs: Vec<u32> = Vec::new();
+
+        while glyphs.len() < 26 {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+
+            let glyph = seed & 0x3fff;
+            if glyph.count_ones() >= 3 && !glyphs.contains(&glyph) {
+                glyphs.push(glyph);
+            }
+        }
+
+        let table_str: String = glyphs
+            .iter()
+            .enumerate()
+            .map(|(n, glyph)| format!("{} {}\n", (b'A' + n as u8) as char, super::mask_letters(*glyph)))
+            .collect();
+        let table: SegmentTable = table_str.parse().unwrap();
+        assert_eq!(table.segment_count(), 14);
+
+        // Scramble the wires and check the decoder puts them back
+        let wiring = Wiring((0..14).map(|n| (n * 5 + 3) % 14).collect());
+        let mut unwire = vec![0u8; 14];
+        for (wire, segment) in wiring.0.iter().enumerate() {
+            unwire[*segment as usize] = wire as u8;
+        }
+        let unwire = Wiring(unwire);
+
+        let patterns: Vec<String> = glyphs.iter().map(|glyph| super::mask_letters(unwire.apply(*glyph))).collect();
+
+        assert_eq!(solve_wiring(&table, &patterns), Ok(wiring));
+        assert_eq!(
+            decode_entry(&table, &patterns, &patterns[7..12]).unwrap(),
+            "HIJKL"
+        );
+    }
+}
@@ -0,0 +1,268 @@
+use std::fmt::Write;
+
+use ndarray::Array2;
+
+use crate::{get_neighbors, Map, Vec2};
+
+/// Height that walls off basins from each other
+const WALL: i32 = 9;
+
+type Cell = (usize, usize);
+
+/// Union-find over every cell in the map
+struct DisjointSet {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(len: usize) -> Self {
+        DisjointSet {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+        }
+    }
+
+    fn find(&mut self, mut n: usize) -> usize {
+        while self.parent[n] != n {
+            self.parent[n] = self.parent[self.parent[n]];
+            n = self.parent[n];
+        }
+
+        n
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+
+        if a == b {
+            return;
+        }
+
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+    }
+}
+
+/// Join every pair of neighboring cells that `joined` accepts, cells are
+/// numbered in reading order
+fn join_cells(map: &Map, joined: impl Fn(i32, i32) -> bool) -> DisjointSet {
+    let (row_count, col_count) = map.dim();
+    let mut set = DisjointSet::new(row_count * col_count);
+
+    for ((row, col), height) in map.indexed_iter() {
+        // Only look right and down, the other two get joined from the far side
+        for neighbor in get_neighbors(Vec2::new(row as i32, col as i32), row_count, col_count)
+            .filter(|n| n[0] as usize >= row && n[1] as usize >= col)
+        {
+            let (n_row, n_col) = (neighbor[0] as usize, neighbor[1] as usize);
+
+            if joined(*height, map[(n_row, n_col)]) {
+                set.union(row * col_count + col, n_row * col_count + n_col);
+            }
+        }
+    }
+
+    set
+}
+
+/// Group cells by the root they share, skipping walls
+fn group_cells(map: &Map, set: &mut DisjointSet) -> Vec<Vec<Cell>> {
+    let col_count = map.dim().1;
+    let mut group_of_root = vec![None; set.parent.len()];
+    let mut groups: Vec<Vec<Cell>> = Vec::new();
+
+    for ((row, col), height) in map.indexed_iter() {
+        if *height == WALL {
+            continue;
+        }
+
+        let root = set.find(row * col_count + col);
+        let group = *group_of_root[root].get_or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+
+        groups[group].push((row, col));
+    }
+
+    groups
+}
+
+/// Connected cells of the same height with nothing lower next to them.
+/// Usually a single cell but flat bottomed basins have more.
+pub fn low_regions(map: &Map) -> Vec<Vec<Cell>> {
+    let (row_count, col_count) = map.dim();
+    let mut plateaus = join_cells(map, |a, b| a == b && a != WALL);
+
+    group_cells(map, &mut plateaus)
+        .into_iter()
+        .filter(|region| {
+            // Neighbors at the same height are part of the region itself
+            let height = map[region[0]];
+
+            region.iter().all(|(row, col)| {
+                get_neighbors(Vec2::new(*row as i32, *col as i32), row_count, col_count)
+                    .all(|n| map[(n[0] as usize, n[1] as usize)] >= height)
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Basin {
+    pub size: usize,
+    /// First of the lowest cells in reading order
+    pub low_point: Cell,
+    pub low_height: i32,
+    /// Every cell connected to the low point at the same height
+    pub low_region: Vec<Cell>,
+    /// Top left and bottom right corners, inclusive
+    pub min: Cell,
+    pub max: Cell,
+}
+
+/// Every non-wall cell labeled with the index of the basin it drains into
+pub struct BasinMap {
+    pub labels: Array2<Option<usize>>,
+    pub basins: Vec<Basin>,
+}
+
+/// Basins are everything that's connected without crossing a wall
+pub fn label_basins(map: &Map) -> BasinMap {
+    let mut set = join_cells(map, |a, b| a != WALL && b != WALL);
+    let mut plateaus = join_cells(map, |a, b| a == b && a != WALL);
+    let col_count = map.dim().1;
+
+    let mut labels = Array2::from_elem(map.dim(), None);
+
+    let basins = group_cells(map, &mut set)
+        .into_iter()
+        .enumerate()
+        .map(|(id, cells)| {
+            for cell in cells.iter() {
+                labels[*cell] = Some(id);
+            }
+
+            // Cells are already in reading order so min keeps the first of any ties
+            let low_point = *cells.iter().min_by_key(|cell| map[**cell]).unwrap();
+            let low_root = plateaus.find(low_point.0 * col_count + low_point.1);
+
+            let low_region = cells
+                .iter()
+                .copied()
+                .filter(|(row, col)| plateaus.find(row * col_count + col) == low_root)
+                .collect();
+
+            Basin {
+                size: cells.len(),
+                low_point,
+                low_height: map[low_point],
+                low_region,
+                min: (
+                    cells.iter().map(|c| c.0).min().unwrap(),
+                    cells.iter().map(|c| c.1).min().unwrap(),
+                ),
+                max: (
+                    cells.iter().map(|c| c.0).max().unwrap(),
+                    cells.iter().map(|c| c.1).max().unwrap(),
+                ),
+            }
+        })
+        .collect();
+
+    BasinMap { labels, basins }
+}
+
+impl BasinMap {
+    /// Basin sizes largest first
+    pub fn sizes(&self) -> Vec<usize> {
+        let mut sizes: Vec<usize> = self.basins.iter().map(|basin| basin.size).collect();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes
+    }
+
+    /// Heights drawn on a background color picked by basin, walls are left plain
+    pub fn render(&self, map: &Map) -> String {
+        const COLORS: [u8; 6] = [41, 42, 43, 44, 45, 46];
+
+        let mut out = String::new();
+
+        for (row, labels) in self.labels.rows().into_iter().enumerate() {
+            for (col, label) in labels.iter().enumerate() {
+                match label {
+                    Some(id) => write!(out, "\x1b[30;{}m{}\x1b[0m", COLORS[id % COLORS.len()], map[(row, col)]).unwrap(),
+                    None => write!(out, "{}", map[(row, col)]).unwrap(),
+                }
+            }
+
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ndarray::array;
+
+    use super::{label_basins, low_regions};
+    use crate::test::get_test_input;
+
+    #[test]
+    fn test_example() {
+        let map = get_test_input();
+
+        let lows = low_regions(&map);
+        assert_eq!(lows.len(), 4);
+        assert_eq!(lows.iter().map(|region| map[region[0]] + 1).sum::<i32>(), 15);
+
+        let basins = label_basins(&map);
+        assert_eq!(basins.basins.len(), 4);
+        assert_eq!(basins.sizes()[..3].iter().product::<usize>(), 1134);
+
+        let top_left = &basins.basins[0];
+        assert_eq!(top_left.size, 3);
+        assert_eq!(top_left.low_point, (0, 1));
+        assert_eq!((top_left.min, top_left.max), ((0, 0), (1, 1)));
+        assert_eq!(basins.labels[(0, 0)], Some(0));
+        assert_eq!(basins.labels[(0, 2)], None);
+    }
+
+    #[test]
+    fn test_plateau() {
+        let map = array![
+            [9, 9, 9, 9, 9],
+            [9, 0, 0, 3, 9],
+            [9, 1, 0, 2, 9],
+            [9, 9, 9, 9, 9],
+        ];
+
+        let lows = low_regions(&map);
+        assert_eq!(lows, vec![vec![(1, 1), (1, 2), (2, 2)]]);
+
+        let basins = label_basins(&map);
+        assert_eq!(basins.basins.len(), 1);
+        assert_eq!(basins.basins[0].size, 6);
+        assert_eq!(basins.basins[0].low_point, (1, 1));
+        assert_eq!(basins.basins[0].low_region, vec![(1, 1), (1, 2), (2, 2)]);
+
+        // A flat shelf next to something lower isn't a low region
+        let shelf = array![[1, 1, 0], [9, 9, 9]];
+        assert_eq!(low_regions(&shelf), vec![vec![(0, 2)]]);
+    }
+
+    #[test]
+    fn test_render() {
+        let map = get_test_input();
+        let rendered = label_basins(&map).render(&map);
+
+        assert_eq!(rendered.lines().count(), 5);
+        assert!(rendered.starts_with("\x1b[30;41m2\x1b[0m\x1b[30;41m1\x1b[0m9"));
+    }
+}
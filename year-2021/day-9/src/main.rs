@@ -1,4 +1,8 @@
-use std::{io::{self, BufRead}, collections::{HashSet, VecDeque}};
+mod basins;
+
+use std::io::{self, BufRead};
+
+use basins::{label_basins, low_regions};
 
 use nalgebra::DimMax;
 use ndarray::{Array2, Array1, s};
@@ -58,80 +62,23 @@ fn main() {
 
     dbg!(&input);
 
-    // for window in input.windows((3, 3)).into_iter() {
-    //     dbg!(window);
-    // }
-    let shape = input.shape();
-
-    let row_count = shape[0];
-    let col_count = shape[1];
-
-    let mut low_points = 0;
-    let mut risk = 0;
-    let mut basin_sizes = Vec::new();
-
-    for row in 0..(row_count) {
-        for col in 0..(col_count) {
-            let current = input[(row, col)];
-
-            let neighbors = [
-                if row > 0 { input.get((row - 1, col)) } else { None },
-                input.get((row + 1, col)),
-                if col > 0 { input.get((row, col - 1)) } else { None },
-                input.get((row as usize, col + 1))
-            ];
-
-            //dbg!(neighbors);
-
-            let low_point = neighbors.iter().copied().filter_map(|n| n).all(|n| *n > current);
-
-            //dbg!(col, row, current, low_point);
-
-            if low_point {
-                low_points += 1;
-                risk += current + 1;
+    let lows = low_regions(&input);
 
-                let mut basin_points = HashSet::new();
-
-                let cur_pos = Vec2::new(row as i32, col as i32);
-                let mut to_visit = VecDeque::new();
-                basin_points.insert(cur_pos);
-                to_visit.push_back(cur_pos);
-
-                while let Some(cur_pos) = to_visit.pop_front() {
-                    for neighbor in get_neighbors(cur_pos, row_count, col_count) {
-                        if basin_points.contains(&neighbor) {
-                            continue;
-                        }
-
-                        let neighbor_val = input[(neighbor[0] as usize, neighbor[1] as usize)];
-
-                        if neighbor_val < 9 {
-                            basin_points.insert(neighbor);
-                            to_visit.push_back(neighbor);
-                        }
-                    }
-                }
-
-                //dbg!(basin_points.len());
-                basin_sizes.push(basin_points.len());
-            }
-
-            // let row_start = i32::max(0, row - 1);
-            // let col_start = i32::
-            // let window = input.slice(s![row_start..(row+2), (col-1)..(col+2)]);
-            // dbg!(window);
-        }
-    }
+    let low_points = lows.len();
+    let risk: i32 = lows.iter().map(|region| input[region[0]] + 1).sum();
 
     dbg!(low_points);
     dbg!(risk);
 
-    basin_sizes.sort();
+    let basin_map = label_basins(&input);
+
+    print!("{}", basin_map.render(&input));
+
+    let basin_sizes = basin_map.sizes();
 
     dbg!(&basin_sizes);
 
-    let top_3_basins: Vec<_> = basin_sizes.iter().rev().take(3).collect();
+    let top_3_basins: Vec<_> = basin_sizes.iter().take(3).collect();
 
     dbg!(top_3_basins);
 }
@@ -142,7 +89,7 @@ mod test {
 
     use crate::{parse_input, Input};
 
-    fn get_test_input() -> Input {
+    pub(crate) fn get_test_input() -> Input {
         let test_data_str = include_str!("../test_input.txt");
 
         let test_data_reader = Cursor::new(test_data_str.to_owned());